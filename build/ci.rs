@@ -0,0 +1,17 @@
+// Environment variables set by common CI services. Checkouts in CI don't need hooks. The integration
+// tests also include this file to remove the variables when running cargo
+pub const CI_VARS: &[&str] = &[
+    "CI",
+    "CONTINUOUS_INTEGRATION",
+    "GITHUB_ACTIONS",
+    "GITLAB_CI",
+    "TRAVIS",
+    "CIRCLECI",
+    "APPVEYOR",
+    "BUILDKITE",
+    "DRONE",
+    "JENKINS_URL",
+    "TEAMCITY_VERSION",
+    "TF_BUILD",
+    "BITBUCKET_BUILD_NUMBER",
+];
@@ -0,0 +1,1239 @@
+// Configuration in `[package.metadata.husky]` table of Cargo.toml or `.cargo-husky.toml` in the
+// repository root. `.cargo-husky.toml` has the same structure as the table. In a workspace,
+// `[workspace.metadata.husky]` table of the root Cargo.toml is inherited by the members and
+// `[package.metadata.husky]` table of each member overrides it.
+//
+// ```toml
+// [package.metadata.husky.hooks]
+// pre-commit = ["fmt"]
+// pre-push = ["test", "clippy", "deny"]
+//
+// [package.metadata.husky.commands.test]
+// args = ["--all-features", "--", "--skip", "slow_"]
+//
+// [package.metadata.husky.commands.clippy]
+// args = ["--all-targets", "--all-features", "--", "-D", "warnings"]
+// fix = true
+// after = ["fmt"]
+//
+// [package.metadata.husky.commands.fmt]
+// fix = true
+// toolchain = "nightly"
+//
+// [package.metadata.husky.commands.deny]
+// checks = ["advisories", "bans"]
+//
+// [package.metadata.husky.commands.hack]
+// args = ["--feature-powerset", "--depth", "2"]
+//
+// [package.metadata.husky.commands.unused-deps]
+// tool = "udeps"
+// toolchain = "nightly"
+//
+// [package.metadata.husky.commands.coverage]
+// tool = "tarpaulin"
+// threshold = 80
+//
+// [package.metadata.husky.commands.miri]
+// args = ["--test", "unsafe_code"]
+//
+// [package.metadata.husky.commands.bench]
+// threshold = 10
+//
+// [package.metadata.husky.commands.target-check]
+// targets = ["wasm32-unknown-unknown"]
+//
+// [package.metadata.husky.commands.no-std-check]
+// features = ["alloc"]
+//
+// [package.metadata.husky.commands.license]
+// allowlist = "licenses.txt"
+//
+// [package.metadata.husky.commands.geiger]
+// budget = 100
+//
+// [package.metadata.husky.profiles.fast.hooks]
+// pre-push = ["fmt"]
+//
+// [package.metadata.husky.env]
+// RUST_BACKTRACE = "1"
+//
+// [package.metadata.husky.env.pre-push]
+// DATABASE_URL = "${DATABASE_URL:-postgres://localhost/test}"
+//
+// [package.metadata.husky]
+// header = ". ./scripts/prelude.sh"
+// footer = "echo '{{hook_name}} passed'"
+// shell = "/usr/bin/env bash"
+// ```
+
+use script::{command_names, default_commands, is_command, quote};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use toml::{self, Table, Value};
+use yaml;
+use {rerun_if_changed, Error, Result, HOOKS};
+
+// Configuration file in the repository root. It is used instead of `[package.metadata.husky]`
+const CONFIG_FILE: &str = ".cargo-husky.toml";
+
+// Configuration of pre-commit framework imported by `import-pre-commit` feature
+const PRE_COMMIT_CONFIG: &str = ".pre-commit-config.yaml";
+
+// Languages of pre-commit hooks which run the entry as a command without installing environment
+const PRE_COMMIT_LANGUAGES: &[&str] = &["system", "script", "unsupported", "unsupported_script"];
+
+// Categories which can be checked by `cargo deny check`
+const DENY_CHECKS: &[&str] = &["advisories", "bans", "licenses", "sources"];
+
+// Commands which do not run cargo
+const NON_CARGO_COMMANDS: &[&str] = &["msrv-check", "typos", "taplo-fmt"];
+
+// Tools which can find unused dependencies
+const UNUSED_DEPS_TOOLS: &[&str] = &["machete", "udeps"];
+
+// Shell languages which custom commands can be written in
+const DIALECTS: &[&str] = &["sh", "powershell", "fish", "nushell"];
+
+// Tools which can measure code coverage
+const COVERAGE_TOOLS: &[&str] = &["llvm-cov", "tarpaulin"];
+
+// Settings of each command in `commands` table
+#[derive(Clone, Default)]
+pub struct CommandConfig {
+    // Categories checked by `cargo deny check`. All categories are checked when empty
+    pub checks: Vec<String>,
+    // Arguments passed to the command instead of its default arguments
+    pub args: Option<Vec<String>>,
+    // Tool to run for the command when it can be run by several tools
+    pub tool: Option<String>,
+    // Toolchain to run the command with such as `nightly`
+    pub toolchain: Option<String>,
+    // Minimum percentage such as line coverage
+    pub threshold: Option<f64>,
+    // Name of baseline which benchmarks are compared with
+    pub baseline: Option<String>,
+    // Target triples to check the crate for
+    pub targets: Option<Vec<String>>,
+    // Features enabled on checking the crate
+    pub features: Vec<String>,
+    // Path to the file which lists allowed licenses
+    pub allowlist: Option<String>,
+    // Maximum number of unsafe code usages
+    pub budget: Option<u64>,
+    // Fixes problems automatically and stages the fixed files again
+    pub fix: bool,
+    // Shell command run by custom command
+    pub run: Option<String>,
+    // Shell language of `run` such as `powershell`. Hooks themselves are always sh scripts
+    pub dialect: Option<String>,
+    // Commands which must run before this command when they are run in the same hook
+    pub after: Vec<String>,
+    // Patterns of paths. The command runs only when a file matching one of them was changed
+    pub paths: Vec<String>,
+}
+
+#[derive(Default)]
+pub struct Config {
+    // Commands to run in each hook. Hooks which are not configured run commands enabled by features
+    hooks: BTreeMap<String, Vec<String>>,
+    commands: BTreeMap<String, CommandConfig>,
+    // Commands to run in each hook when the profile is selected by `$HUSKY_PROFILE` at runtime.
+    // Hooks which are not configured in the profile run the same commands as without profile
+    profiles: BTreeMap<String, BTreeMap<String, Vec<String>>>,
+    // Environment variables set in all hooks
+    env: BTreeMap<String, String>,
+    // Environment variables set in each hook. They override variables set in all hooks
+    hook_env: BTreeMap<String, BTreeMap<String, String>>,
+    // Shell script put at the beginning of hooks after the version comment
+    pub header: Option<String>,
+    // Shell script put at the end of hooks. It runs after all commands succeeded
+    pub footer: Option<String>,
+    // Interpreter in the shebang of hooks such as `/usr/bin/env bash`
+    shell: Option<String>,
+    // Interpreter of each hook. It overrides `shell`
+    hook_shell: BTreeMap<String, String>,
+    // Repository to install hooks relative to the repository root. It selects one of nested
+    // repositories in a monorepo
+    pub repository: Option<String>,
+    // `rust-version` in Cargo.toml
+    pub rust_version: Option<String>,
+}
+
+// Looks up the table at the dotted path
+fn get<'a>(mut table: &'a Table, path: &[&str]) -> Option<&'a Value> {
+    let (last, path) = path.split_last()?;
+    for key in path {
+        table = match table.get(*key) {
+            Some(Value::Table(t)) => t,
+            _ => return None,
+        };
+    }
+    table.get(*last)
+}
+
+// Looks up the string field of `[package]` such as `rust-version`
+fn package_field(root: &Table, key: &str) -> Option<String> {
+    match get(root, &["package", key]) {
+        Some(Value::String(v)) => Some(v.clone()),
+        // `rust-version.workspace = true` inherits the field from workspace
+        Some(Value::Table(_)) | None => match get(root, &["workspace", "package", key]) {
+            Some(Value::String(v)) => Some(v.clone()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// Names of binaries declared by `[[bin]]` tables in the manifest
+pub fn bin_names(manifest: &Path) -> Result<Vec<String>> {
+    let bins = match parse_file(manifest)? {
+        Some(root) => match root.get("bin") {
+            Some(Value::Array(bins)) => bins.clone(),
+            _ => vec![],
+        },
+        None => vec![],
+    };
+    Ok(bins
+        .iter()
+        .filter_map(|bin| match bin {
+            Value::Table(t) => match t.get("name") {
+                Some(Value::String(name)) => Some(name.clone()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect())
+}
+
+// `version` of the package in Cargo.toml in the repository root directory
+pub fn crate_version(root_dir: &Path) -> Result<Option<String>> {
+    let cargo_toml = parse_file(&root_dir.join("Cargo.toml"))?;
+    Ok(cargo_toml.and_then(|root| package_field(&root, "version")))
+}
+
+// Checks the dependency tables of the manifest declare cargo-husky. Renamed dependencies are
+// detected by `package` key
+fn declares_cargo_husky(manifest: &Table) -> bool {
+    let declares = |deps: Option<&Value>| match deps {
+        Some(Value::Table(deps)) => deps.iter().any(|(name, dep)| match dep {
+            Value::Table(dep) => match dep.get("package") {
+                Some(Value::String(package)) => package == "cargo-husky",
+                _ => name == "cargo-husky",
+            },
+            _ => name == "cargo-husky",
+        }),
+        _ => false,
+    };
+    let kinds = ["dependencies", "dev-dependencies", "build-dependencies"];
+    let targets = match manifest.get("target") {
+        Some(Value::Table(targets)) => targets.values().collect(),
+        _ => vec![],
+    };
+    declares(get(manifest, &["workspace", "dependencies"]))
+        || kinds.iter().any(|kind| {
+            declares(manifest.get(*kind))
+                || targets.iter().any(|target| match target {
+                    Value::Table(target) => declares(target.get(*kind)),
+                    _ => false,
+                })
+        })
+}
+
+// Checks the package or the workspace whose manifest is put in the directory depends on
+// cargo-husky directly or is cargo-husky itself. `None` is returned when the directory has no
+// Cargo.toml
+pub fn depends_on_cargo_husky(dir: &Path) -> Result<Option<bool>> {
+    let root = match parse_file(&dir.join("Cargo.toml"))? {
+        Some(root) => root,
+        None => return Ok(None),
+    };
+    let is_self = match get(&root, &["package", "name"]) {
+        Some(Value::String(name)) => name == "cargo-husky",
+        _ => false,
+    };
+    if is_self || declares_cargo_husky(&root) {
+        return Ok(Some(true));
+    }
+    for member in member_manifests(dir, &root)? {
+        if let Some(member) = parse_file(&member)? {
+            if declares_cargo_husky(&member) {
+                return Ok(Some(true));
+            }
+        }
+    }
+    Ok(Some(false))
+}
+
+// Parses the TOML file. `None` is returned when the file does not exist
+fn parse_file(path: &Path) -> Result<Option<Table>> {
+    rerun_if_changed(path);
+    let src = match fs::read_to_string(path) {
+        Ok(src) => src,
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(Error::Io(err)),
+    };
+    toml::parse(&src)
+        .map(Some)
+        .map_err(|err| Error::InvalidManifest(path.to_owned(), err))
+}
+
+// Converts local hooks in `.pre-commit-config.yaml` to a table of cargo-husky configuration. Each
+// hook becomes a custom command run in the hooks of its stages after the commands enabled by
+// features. Hooks which cannot be converted are skipped with warnings. `None` is returned when the
+// file does not exist
+fn import_pre_commit_config(path: &Path) -> Result<Option<Table>> {
+    rerun_if_changed(path);
+    let src = match fs::read_to_string(path) {
+        Ok(src) => src,
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(Error::Io(err)),
+    };
+    let yaml = yaml::parse(&src).map_err(|err| Error::InvalidManifest(path.to_owned(), err))?;
+    let loader = Loader {
+        file: path,
+        prefix: "",
+    };
+    let warn = |message: String| println!("cargo:warning={} in {:?}", message, path);
+
+    let string = |key: &str, value: Option<&Value>| match value {
+        Some(value) => loader.string(key, value).map(Some),
+        None => Ok(None),
+    };
+    let strings = |key: &str, value: Option<&Value>| match value {
+        Some(value) => loader.strings(key, value).map(Some),
+        None => Ok(None),
+    };
+    let default_stages = strings("default_stages", yaml.get("default_stages"))?
+        .unwrap_or_else(|| vec!["pre-commit".to_string()]);
+    let repos = match yaml.get("repos") {
+        Some(Value::Array(repos)) => repos.as_slice(),
+        Some(v) => {
+            return loader.error("repos", format!("expected array but got {}", v.type_name()))
+        }
+        None => &[],
+    };
+
+    let mut commands = Table::new();
+    let mut hooks = BTreeMap::<String, Vec<Value>>::new();
+    for (i, repo) in repos.iter().enumerate() {
+        let key = format!("repos[{}]", i);
+        let repo = loader.table(&key, repo)?;
+        match string(&format!("{}.repo", key), repo.get("repo"))? {
+            Some(ref url) if url == "local" => {}
+            Some(url) => {
+                warn(format!(
+                    "Hooks of repository '{}' were not imported. Only hooks of `repo: local` can be imported",
+                    url
+                ));
+                continue;
+            }
+            None => return loader.error(&key, "'repo' is not set"),
+        }
+        let entries = match repo.get("hooks") {
+            Some(Value::Array(entries)) => entries.as_slice(),
+            Some(v) => {
+                let key = format!("{}.hooks", key);
+                return loader.error(&key, format!("expected array but got {}", v.type_name()));
+            }
+            None => &[],
+        };
+        for (j, hook) in entries.iter().enumerate() {
+            let key = format!("{}.hooks[{}]", key, j);
+            let hook = loader.table(&key, hook)?;
+            let field = |name: &str| string(&format!("{}.{}", key, name), hook.get(name));
+            let id = match field("id")? {
+                Some(id) => id,
+                None => return loader.error(&key, "'id' is not set"),
+            };
+            let language = field("language")?.unwrap_or_else(|| "system".to_string());
+            if !PRE_COMMIT_LANGUAGES.contains(&language.as_str()) {
+                warn(format!(
+                    "Hook '{}' was not imported since language '{}' is not supported. Supported languages are: {}",
+                    id,
+                    language,
+                    PRE_COMMIT_LANGUAGES.join(", ")
+                ));
+                continue;
+            }
+            let entry = match field("entry")? {
+                Some(entry) => entry,
+                None => return loader.error(&key, format!("'entry' of hook '{}' is not set", id)),
+            };
+            let mut run = entry.trim().to_string();
+            if let Some(args) = strings(&format!("{}.args", key), hook.get("args"))? {
+                for arg in args {
+                    run.push(' ');
+                    run.push_str(&quote(&arg));
+                }
+            }
+            let filters = ["files", "exclude", "types", "types_or", "exclude_types"];
+            let ignored = filters
+                .iter()
+                .filter(|f| hook.contains_key(**f))
+                .map(|f| format!("`{}`", f))
+                .collect::<Vec<_>>();
+            if !ignored.is_empty() {
+                warn(format!(
+                    "Filters {} of hook '{}' were ignored. The command runs regardless of changed files",
+                    ignored.join(", "),
+                    id
+                ));
+            }
+            // pre-commit passes the changed files to the command by default
+            let pass_filenames = match hook.get("pass_filenames") {
+                Some(value) => loader.boolean(&format!("{}.pass_filenames", key), value)?,
+                None => true,
+            };
+            if pass_filenames {
+                warn(format!(
+                    "Hook '{}' is run without filenames as arguments. Set `pass_filenames: false` if it does not need them",
+                    id
+                ));
+            }
+
+            // Builtin commands and hooks sharing the same ID are not overwritten
+            let mut name = id.clone();
+            let mut n = 1;
+            while is_command(&name) || commands.contains_key(&name) {
+                n += 1;
+                name = format!("{}-{}", id, n);
+            }
+            let stages = strings(&format!("{}.stages", key), hook.get("stages"))?
+                .unwrap_or_else(|| default_stages.clone());
+            for stage in stages {
+                // Stages without `pre-` prefix were used before pre-commit v3
+                let stage = match stage.as_str() {
+                    "commit" => "pre-commit",
+                    "push" => "pre-push",
+                    "merge-commit" => "pre-merge-commit",
+                    "manual" => continue,
+                    s => s,
+                };
+                if !HOOKS.iter().any(|(h, _)| *h == stage) {
+                    warn(format!(
+                        "Hook '{}' was not imported to unsupported stage '{}'",
+                        id, stage
+                    ));
+                    continue;
+                }
+                // Imported commands are run after the commands enabled by features
+                let names = hooks.entry(stage.to_string()).or_insert_with(|| {
+                    default_commands(stage)
+                        .into_iter()
+                        .map(|c| Value::String(c.to_string()))
+                        .collect()
+                });
+                names.push(Value::String(name.clone()));
+            }
+            let mut command = Table::new();
+            command.insert("run".to_string(), Value::String(run));
+            commands.insert(name, Value::Table(command));
+        }
+    }
+
+    let mut table = Table::new();
+    table.insert("commands".to_string(), Value::Table(commands));
+    let hooks = hooks.into_iter().map(|(h, c)| (h, Value::Array(c)));
+    table.insert("hooks".to_string(), Value::Table(hooks.collect()));
+    Ok(Some(table))
+}
+
+// Matches the file name with the pattern. `*` in the pattern matches any sequence of characters
+fn matches_glob(pattern: &str, name: &str) -> bool {
+    match pattern.find('*') {
+        None => pattern == name,
+        Some(i) => {
+            let (prefix, rest) = (&pattern[..i], &pattern[i + 1..]);
+            name.starts_with(prefix)
+                && (prefix.len()..=name.len())
+                    .any(|j| name.is_char_boundary(j) && matches_glob(rest, &name[j..]))
+        }
+    }
+}
+
+// Expands the glob pattern in `workspace.members` to directories
+fn expand_glob(root_dir: &Path, pattern: &str) -> Result<Vec<PathBuf>> {
+    let mut dirs = vec![root_dir.to_owned()];
+    for component in pattern.split('/').filter(|c| !c.is_empty() && *c != ".") {
+        let mut expanded = vec![];
+        for dir in dirs {
+            if !component.contains('*') {
+                expanded.push(dir.join(component));
+                continue;
+            }
+            let entries = match fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(ref err) if err.kind() == io::ErrorKind::NotFound => continue,
+                Err(err) => return Err(Error::Io(err)),
+            };
+            for entry in entries {
+                let entry = entry?;
+                if entry.file_type()?.is_dir()
+                    && matches_glob(component, &entry.file_name().to_string_lossy())
+                {
+                    expanded.push(entry.path());
+                }
+            }
+        }
+        dirs = expanded;
+    }
+    Ok(dirs)
+}
+
+// Manifests of workspace members in the repository root except for the root package
+fn member_manifests(root_dir: &Path, root: &Table) -> Result<Vec<PathBuf>> {
+    let patterns = |key| match get(root, &["workspace", key]) {
+        Some(Value::Array(a)) => a
+            .iter()
+            .filter_map(|v| match v {
+                Value::String(s) => Some(s.as_str()),
+                _ => None,
+            })
+            .collect(),
+        _ => vec![],
+    };
+    let mut excluded = vec![];
+    for pattern in patterns("exclude") {
+        excluded.extend(expand_glob(root_dir, pattern)?);
+    }
+    let mut manifests = vec![];
+    for pattern in patterns("members") {
+        for dir in expand_glob(root_dir, pattern)? {
+            if dir != root_dir && !excluded.contains(&dir) {
+                manifests.push(dir.join("Cargo.toml"));
+            }
+        }
+    }
+    // Sort manifests so that the configuration does not depend on the order of directory entries
+    manifests.sort();
+    manifests.dedup();
+    Ok(manifests)
+}
+
+// Keys in the top-level table of configuration
+const KEYS: &[&str] = &[
+    "commands",
+    "env",
+    "footer",
+    "header",
+    "hooks",
+    "profiles",
+    "repository",
+    "shell",
+];
+
+// Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cur = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                prev
+            } else {
+                1 + prev.min(cur).min(row[j])
+            };
+            prev = cur;
+        }
+    }
+    row[b.len()]
+}
+
+// Suggests the most similar candidate for the misspelled name
+fn did_you_mean<'a, I: IntoIterator<Item = &'a str>>(name: &str, candidates: I) -> String {
+    candidates
+        .into_iter()
+        .map(|c| (edit_distance(name, c), c))
+        .filter(|&(d, _)| d <= 2)
+        .min()
+        .map(|(_, c)| format!(". Did you mean '{}'?", c))
+        .unwrap_or_default()
+}
+
+fn is_env_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+// Names of the tables in the table at the key. Non-table values are reported by `Loader`
+fn table_keys<'a>(table: &'a Table, key: &str) -> Vec<&'a str> {
+    match table.get(key) {
+        Some(Value::Table(t)) => t.keys().map(String::as_str).collect(),
+        _ => vec![],
+    }
+}
+
+impl Config {
+    // Loads configuration from `.cargo-husky.toml`, `[workspace.metadata.husky]` or
+    // `[package.metadata.husky]` in Cargo.toml in the repository root directory, then applies
+    // `[package.metadata.husky]` of each workspace member
+    pub fn load(root_dir: &Path) -> Result<Config> {
+        let manifest = root_dir.join("Cargo.toml");
+        let cargo_toml = parse_file(&manifest)?.unwrap_or_default();
+        let file = root_dir.join(CONFIG_FILE);
+
+        let mut config = Config::default();
+        // Hooks imported from pre-commit framework are loaded first so that configuration of
+        // cargo-husky overrides them
+        if cfg!(feature = "import-pre-commit") {
+            let path = root_dir.join(PRE_COMMIT_CONFIG);
+            if let Some(table) = import_pre_commit_config(&path)? {
+                Loader {
+                    file: &path,
+                    prefix: "",
+                }
+                .load(&table, &mut config)?;
+            }
+        }
+        match parse_file(&file)? {
+            Some(table) => {
+                for prefix in &["workspace.metadata.husky", "package.metadata.husky"] {
+                    let path = prefix.split('.').collect::<Vec<_>>();
+                    if get(&cargo_toml, &path).is_some() {
+                        return Err(Error::InvalidConfig {
+                            file: manifest,
+                            key: prefix.to_string(),
+                            message: format!("configuration is also defined in {}", CONFIG_FILE),
+                        });
+                    }
+                }
+                Loader {
+                    file: &file,
+                    prefix: "",
+                }
+                .load(&table, &mut config)?;
+            }
+            None => {
+                for prefix in &["workspace.metadata.husky", "package.metadata.husky"] {
+                    let path = prefix.split('.').collect::<Vec<_>>();
+                    if let Some(value) = get(&cargo_toml, &path) {
+                        let loader = Loader {
+                            file: &manifest,
+                            prefix,
+                        };
+                        loader.load(loader.table("", value)?, &mut config)?;
+                    }
+                }
+            }
+        }
+
+        // Members share the same hooks. Configuring the same hook or command in several members
+        // is rejected since the hook scripts would depend on which member installed them
+        let mut configured_by = BTreeMap::new();
+        for member in member_manifests(root_dir, &cargo_toml)? {
+            let member_toml = match parse_file(&member)? {
+                Some(t) => t,
+                None => continue,
+            };
+            let loader = Loader {
+                file: &member,
+                prefix: "package.metadata.husky",
+            };
+            let table = match get(&member_toml, &["package", "metadata", "husky"]) {
+                Some(value) => loader.table("", value)?,
+                None => continue,
+            };
+            for section in &["hooks", "commands"] {
+                for name in table_keys(table, section) {
+                    let key = format!("{}.{}", section, name);
+                    if let Some(other) = configured_by.insert(key.clone(), member.clone()) {
+                        return loader.error(&key, format!("also configured in {:?}", other));
+                    }
+                }
+            }
+            loader.load(table, &mut config)?;
+        }
+
+        config.rust_version = package_field(&cargo_toml, "rust-version");
+        Ok(config)
+    }
+
+    // Commands run in the hook when the profile is selected
+    fn profile_commands(&self, hook: &str, profile: Option<&str>) -> Vec<&str> {
+        let configured = profile
+            .and_then(|p| self.profiles.get(p))
+            .and_then(|hooks| hooks.get(hook))
+            .or_else(|| self.hooks.get(hook));
+        match configured {
+            Some(commands) => commands.iter().map(String::as_str).collect(),
+            None => default_commands(hook),
+        }
+    }
+
+    // Commands run in the hook with any profile. They are ordered by `after` settings and otherwise
+    // keep the order in the `hooks` table or the default order
+    pub fn commands(&self, hook: &str) -> Vec<&str> {
+        let mut rest = self.profile_commands(hook, None);
+        for profile in self.profiles.keys() {
+            for name in self.profile_commands(hook, Some(profile)) {
+                if !rest.contains(&name) {
+                    rest.push(name);
+                }
+            }
+        }
+        let mut ordered = vec![];
+        while !rest.is_empty() {
+            // Some command is always ready since cycles in `after` settings are rejected on loading
+            let i = rest
+                .iter()
+                .position(|name| self.after(name).iter().all(|a| !rest.contains(&a.as_str())))
+                .unwrap();
+            ordered.push(rest.remove(i));
+        }
+        ordered
+    }
+
+    fn after(&self, name: &str) -> &[String] {
+        self.commands.get(name).map_or(&[], |c| &c.after)
+    }
+
+    // Finds commands which cannot be ordered since they depend on each other through `after`
+    fn find_cycle(&self) -> Option<Vec<&str>> {
+        fn visit<'a>(
+            config: &'a Config,
+            name: &'a str,
+            path: &mut Vec<&'a str>,
+            done: &mut Vec<&'a str>,
+        ) -> bool {
+            if let Some(i) = path.iter().position(|n| *n == name) {
+                path.drain(..i);
+                path.push(name);
+                return true;
+            }
+            if done.contains(&name) {
+                return false;
+            }
+            path.push(name);
+            for a in config.after(name) {
+                if visit(config, a, path, done) {
+                    return true;
+                }
+            }
+            path.pop();
+            done.push(name);
+            false
+        }
+
+        let (mut path, mut done) = (vec![], vec![]);
+        for name in self.commands.keys() {
+            if visit(self, name, &mut path, &mut done) {
+                return Some(path);
+            }
+        }
+        None
+    }
+
+    // Hooks listed in `hooks` table are installed even if they are not enabled by features
+    pub fn has_hook(&self, hook: &str) -> bool {
+        self.hooks.contains_key(hook) || self.profiles.values().any(|p| p.contains_key(hook))
+    }
+
+    pub fn profiles(&self) -> Vec<&str> {
+        self.profiles.keys().map(String::as_str).collect()
+    }
+
+    // Profiles in which the command runs in the hook. Empty string means no profile is selected.
+    // `None` is returned when the command runs regardless of the profile
+    pub fn command_profiles(&self, hook: &str, name: &str) -> Option<Vec<&str>> {
+        let mut profiles = vec![];
+        if self.profile_commands(hook, None).contains(&name) {
+            profiles.push("");
+        }
+        for profile in self.profiles.keys() {
+            if self.profile_commands(hook, Some(profile)).contains(&name) {
+                profiles.push(profile);
+            }
+        }
+        if profiles.len() == self.profiles.len() + 1 {
+            None
+        } else {
+            Some(profiles)
+        }
+    }
+
+    // Environment variables exported in the hook
+    pub fn env(&self, hook: &str) -> BTreeMap<&str, &str> {
+        let mut env = BTreeMap::new();
+        let hook_env = self.hook_env.get(hook).into_iter().flatten();
+        for (name, value) in self.env.iter().chain(hook_env) {
+            env.insert(name.as_str(), value.as_str());
+        }
+        env
+    }
+
+    pub fn command(&self, name: &str) -> CommandConfig {
+        self.commands.get(name).cloned().unwrap_or_default()
+    }
+
+    // Interpreter put in the shebang of the hook
+    pub fn shell(&self, hook: &str) -> &str {
+        self.hook_shell
+            .get(hook)
+            .or(self.shell.as_ref())
+            .map(String::as_str)
+            .unwrap_or("/bin/sh")
+    }
+}
+
+struct Loader<'a> {
+    file: &'a Path,
+    // Path to the table of configuration. Empty when the configuration is the whole file
+    prefix: &'a str,
+}
+
+impl<'a> Loader<'a> {
+    fn error<T, S: Into<String>>(&self, key: &str, message: S) -> Result<T> {
+        Err(Error::InvalidConfig {
+            file: PathBuf::from(self.file),
+            key: if self.prefix.is_empty() {
+                key.to_string()
+            } else if key.is_empty() {
+                self.prefix.to_string()
+            } else {
+                format!("{}.{}", self.prefix, key)
+            },
+            message: message.into(),
+        })
+    }
+
+    fn table<'v>(&self, key: &str, value: &'v Value) -> Result<&'v Table> {
+        match value {
+            Value::Table(t) => Ok(t),
+            v => self.error(key, format!("expected table but got {}", v.type_name())),
+        }
+    }
+
+    fn strings(&self, key: &str, value: &Value) -> Result<Vec<String>> {
+        let values = match value {
+            Value::Array(a) => a,
+            v => return self.error(key, format!("expected array but got {}", v.type_name())),
+        };
+        let mut strings = vec![];
+        for value in values {
+            match value {
+                Value::String(s) => strings.push(s.clone()),
+                v => {
+                    return self.error(
+                        key,
+                        format!(
+                            "expected array of strings but it contains {}",
+                            v.type_name()
+                        ),
+                    )
+                }
+            }
+        }
+        Ok(strings)
+    }
+
+    fn percentage(&self, key: &str, value: &Value) -> Result<f64> {
+        let p = match *value {
+            Value::Integer(i) => i as f64,
+            Value::Float(f) => f,
+            ref v => return self.error(key, format!("expected number but got {}", v.type_name())),
+        };
+        if !(0.0..=100.0).contains(&p) {
+            return self.error(key, format!("percentage must be in 0..100 but got {}", p));
+        }
+        Ok(p)
+    }
+
+    fn unsigned(&self, key: &str, value: &Value) -> Result<u64> {
+        match *value {
+            Value::Integer(i) if i >= 0 => Ok(i as u64),
+            Value::Integer(i) => {
+                self.error(key, format!("expected non-negative integer but got {}", i))
+            }
+            ref v => self.error(key, format!("expected integer but got {}", v.type_name())),
+        }
+    }
+
+    fn boolean(&self, key: &str, value: &Value) -> Result<bool> {
+        match *value {
+            Value::Boolean(b) => Ok(b),
+            ref v => self.error(key, format!("expected boolean but got {}", v.type_name())),
+        }
+    }
+
+    fn string(&self, key: &str, value: &Value) -> Result<String> {
+        match value {
+            Value::String(s) => Ok(s.clone()),
+            v => self.error(key, format!("expected string but got {}", v.type_name())),
+        }
+    }
+
+    // Interpreter of hooks. It is an absolute path optionally followed by arguments as the shebang
+    // line is
+    fn shell(&self, key: &str, value: &Value) -> Result<String> {
+        let shell = self.string(key, value)?;
+        if shell.starts_with("#!") {
+            return self.error(key, "interpreter must not start with '#!'");
+        }
+        if !shell.starts_with('/') {
+            return self.error(
+                key,
+                format!("interpreter must be an absolute path but got '{}'", shell),
+            );
+        }
+        Ok(shell)
+    }
+
+    // Checks `${VAR}` and `${VAR:-default}` in the command are valid. They are substituted by the
+    // shell running hooks
+    fn check_substitutions(&self, key: &str, command: &str) -> Result<()> {
+        let mut rest = command;
+        while let Some(start) = rest.find("${") {
+            rest = &rest[start + 2..];
+            let end = match rest.find('}') {
+                Some(end) => end,
+                None => return self.error(key, format!("unclosed '${{' in '{}'", command)),
+            };
+            let (var, default) = match rest[..end].find(":-") {
+                Some(i) => (&rest[..i], Some(&rest[i + 2..end])),
+                None => (&rest[..end], None),
+            };
+            if !is_env_name(var) || default.is_some_and(|d| d.contains("${")) {
+                return self.error(
+                    key,
+                    format!(
+                        "invalid substitution '${{{}}}'. Only ${{VAR}} and ${{VAR:-default}} are supported",
+                        &rest[..end]
+                    ),
+                );
+            }
+            rest = &rest[end + 1..];
+        }
+        Ok(())
+    }
+
+    // Command line arguments which can contain substitutions
+    fn args(&self, key: &str, value: &Value) -> Result<Vec<String>> {
+        let args = self.strings(key, value)?;
+        for arg in &args {
+            self.check_substitutions(key, arg)?;
+        }
+        Ok(args)
+    }
+
+    fn check_hook(&self, key: &str, hook: &str) -> Result<()> {
+        if HOOKS.iter().any(|(h, _)| *h == hook) {
+            return Ok(());
+        }
+        self.error(
+            key,
+            format!(
+                "unknown hook '{}'{}",
+                hook,
+                did_you_mean(hook, HOOKS.iter().map(|(h, _)| *h))
+            ),
+        )
+    }
+
+    // Loads the table of environment variables. Values can contain substitutions
+    fn env(&self, key: &str, table: &Table, env: &mut BTreeMap<String, String>) -> Result<()> {
+        for (name, value) in table {
+            let key = format!("{}.{}", key, name);
+            if !is_env_name(name) {
+                return self.error(
+                    &key,
+                    format!("invalid environment variable name '{}'", name),
+                );
+            }
+            let value = self.string(&key, value)?;
+            self.check_substitutions(&key, &value)?;
+            env.insert(name.clone(), value);
+        }
+        Ok(())
+    }
+
+    // Checks the value is one of the candidates
+    fn check_choice(&self, key: &str, value: &str, candidates: &[&str]) -> Result<()> {
+        if candidates.contains(&value) {
+            return Ok(());
+        }
+        self.error(
+            key,
+            format!(
+                "unknown value '{}'. Available values are: {}",
+                value,
+                candidates.join(", ")
+            ),
+        )
+    }
+
+    fn choice(&self, key: &str, value: &Value, candidates: &[&str]) -> Result<String> {
+        let value = self.string(key, value)?;
+        self.check_choice(key, &value, candidates)?;
+        Ok(value)
+    }
+
+    // Checks all elements of the array are one of the candidates
+    fn choices(&self, key: &str, value: &Value, candidates: &[&str]) -> Result<Vec<String>> {
+        let values = self.strings(key, value)?;
+        for v in &values {
+            self.check_choice(key, v, candidates)?;
+        }
+        Ok(values)
+    }
+
+    // Loads the table onto the configuration. Settings in the table override the inherited ones
+    fn load(&self, table: &Table, config: &mut Config) -> Result<()> {
+        if let Some(key) = table.keys().find(|k| !KEYS.contains(&k.as_str())) {
+            return self.error(
+                key,
+                format!(
+                    "unknown key '{}'{}. Available keys are: {}",
+                    key,
+                    did_you_mean(key, KEYS.iter().cloned()),
+                    KEYS.join(", ")
+                ),
+            );
+        }
+        if let Some(commands) = table.get("commands") {
+            for (name, settings) in self.table("commands", commands)? {
+                let key = format!("commands.{}", name);
+                let inherited = config.command(name);
+                let settings = self.command(&key, name, self.table(&key, settings)?, inherited)?;
+                if !is_command(name) && settings.run.is_none() {
+                    return self.error(
+                        &key,
+                        format!(
+                            "unknown command '{}'. Set 'run' to define a custom command",
+                            name
+                        ),
+                    );
+                }
+                config.commands.insert(name.clone(), settings);
+            }
+            // `after` can refer to custom commands defined after the command
+            for name in self.table("commands", commands)?.keys() {
+                if let Some(a) = config
+                    .after(name)
+                    .iter()
+                    .find(|a| !is_command(a) && !config.commands.contains_key(*a))
+                {
+                    let key = format!("commands.{}.after", name);
+                    return self.error(&key, format!("unknown command '{}'", a));
+                }
+            }
+            if let Some(cycle) = config.find_cycle() {
+                return self.error(
+                    &format!("commands.{}.after", cycle[0]),
+                    format!("commands depend on each other: {}", cycle.join(" -> ")),
+                );
+            }
+        }
+        // String values are set in all hooks and tables are set in the hook of the table name
+        if let Some(env) = table.get("env") {
+            let mut global = vec![];
+            for (name, value) in self.table("env", env)? {
+                match value {
+                    Value::Table(t) => {
+                        let key = format!("env.{}", name);
+                        self.check_hook(&key, name)?;
+                        self.env(&key, t, config.hook_env.entry(name.clone()).or_default())?;
+                    }
+                    _ => global.push((name.clone(), value.clone())),
+                }
+            }
+            self.env("env", &global.into_iter().collect(), &mut config.env)?;
+        }
+        // Header and footer of workspace members override the ones of the root
+        if let Some(header) = table.get("header") {
+            config.header = Some(self.string("header", header)?);
+        }
+        if let Some(footer) = table.get("footer") {
+            config.footer = Some(self.string("footer", footer)?);
+        }
+        if let Some(repository) = table.get("repository") {
+            let path = self.string("repository", repository)?;
+            if path.is_empty() || Path::new(&path).is_absolute() {
+                return self.error(
+                    "repository",
+                    format!(
+                        "repository must be a relative path from the repository root but got '{}'",
+                        path
+                    ),
+                );
+            }
+            config.repository = Some(path);
+        }
+        // String value is the interpreter of all hooks and table is the interpreters of each hook
+        match table.get("shell") {
+            Some(Value::Table(t)) => {
+                for (hook, value) in t {
+                    let key = format!("shell.{}", hook);
+                    self.check_hook(&key, hook)?;
+                    let shell = self.shell(&key, value)?;
+                    config.hook_shell.insert(hook.clone(), shell);
+                }
+            }
+            Some(value) => config.shell = Some(self.shell("shell", value)?),
+            None => {}
+        }
+        if let Some(hooks) = table.get("hooks") {
+            config.hooks = self.hooks("hooks", hooks, config, config.hooks.clone())?;
+        }
+        if let Some(profiles) = table.get("profiles") {
+            for (profile, settings) in self.table("profiles", profiles)? {
+                let key = format!("profiles.{}", profile);
+                if profile.is_empty()
+                    || !profile
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+                {
+                    return self.error(&key, format!("invalid profile name '{}'", profile));
+                }
+                for (name, value) in self.table(&key, settings)? {
+                    let key = format!("{}.{}", key, name);
+                    if name != "hooks" {
+                        return self.error(&key, "unknown setting for profile");
+                    }
+                    let inherited = config.profiles.get(profile).cloned().unwrap_or_default();
+                    let hooks = self.hooks(&key, value, config, inherited)?;
+                    config.profiles.insert(profile.clone(), hooks);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Loads the table of commands run in each hook. Settings in the table override the inherited
+    // ones
+    fn hooks(
+        &self,
+        key: &str,
+        value: &Value,
+        config: &Config,
+        mut configured: BTreeMap<String, Vec<String>>,
+    ) -> Result<BTreeMap<String, Vec<String>>> {
+        for (hook, commands) in self.table(key, value)? {
+            let key = format!("{}.{}", key, hook);
+            self.check_hook(&key, hook)?;
+            let commands = self.strings(&key, commands)?;
+            if let Some((_, name)) = commands
+                .iter()
+                .enumerate()
+                .find(|&(i, name)| commands[..i].contains(name))
+            {
+                return self.error(&key, format!("command '{}' is listed more than once", name));
+            }
+            if let Some(name) = commands
+                .iter()
+                .find(|name| !is_command(name) && !config.commands.contains_key(*name))
+            {
+                let mut available = command_names();
+                available.extend(config.commands.keys().map(String::as_str));
+                available.sort();
+                available.dedup();
+                return self.error(
+                    &key,
+                    format!(
+                        "unknown command '{}'{}. Available commands are: {}",
+                        name,
+                        did_you_mean(name, available.iter().cloned()),
+                        available.join(", ")
+                    ),
+                );
+            }
+            configured.insert(hook.clone(), commands);
+        }
+        Ok(configured)
+    }
+
+    fn command(
+        &self,
+        prefix: &str,
+        command: &str,
+        table: &Table,
+        mut config: CommandConfig,
+    ) -> Result<CommandConfig> {
+        for (name, value) in table {
+            let key = format!("{}.{}", prefix, name);
+            match (command, name.as_str()) {
+                (_, "after") => config.after = self.strings(&key, value)?,
+                (_, "paths") => {
+                    let paths = self.strings(&key, value)?;
+                    if paths.is_empty() || paths.iter().any(String::is_empty) {
+                        return self.error(&key, "path patterns must not be empty");
+                    }
+                    for p in &paths {
+                        if p.starts_with('/') || p.starts_with("./") || p.starts_with("../") {
+                            return self.error(
+                                &key,
+                                format!(
+                                    "path pattern '{}' must be relative to the repository root such as 'src/*'",
+                                    p
+                                ),
+                            );
+                        }
+                        if p.ends_with('/') {
+                            return self.error(
+                                &key,
+                                format!(
+                                    "path pattern '{}' only matches directories. Use '{}*' to match files in the directory",
+                                    p, p
+                                ),
+                            );
+                        }
+                    }
+                    config.paths = paths;
+                }
+                (c, "run") if !is_command(c) => {
+                    let run = self.string(&key, value)?;
+                    if run.trim().is_empty() {
+                        return self.error(&key, "command must not be empty");
+                    }
+                    self.check_substitutions(&key, &run)?;
+                    config.run = Some(run);
+                }
+                (c, "dialect") if !is_command(c) => {
+                    config.dialect = Some(self.choice(&key, value, DIALECTS)?)
+                }
+                // Commands not run by cargo cannot select toolchain
+                (c, "toolchain") if is_command(c) && !NON_CARGO_COMMANDS.contains(&c) => {
+                    let toolchain = self.string(&key, value)?;
+                    // The name is put in hook scripts as is
+                    let valid = |c: char| c.is_ascii_alphanumeric() || "._-".contains(c);
+                    if toolchain.is_empty() || !toolchain.chars().all(valid) {
+                        return self.error(
+                            &key,
+                            format!(
+                                "invalid toolchain '{}'. Specify toolchain name without '+' such as 'nightly'",
+                                toolchain
+                            ),
+                        );
+                    }
+                    config.toolchain = Some(toolchain);
+                }
+                ("deny", "checks") => config.checks = self.choices(&key, value, DENY_CHECKS)?,
+                ("clippy", "fix") | ("fmt", "fix") => config.fix = self.boolean(&key, value)?,
+                ("test", "args") | ("clippy", "args") | ("hack", "args") => {
+                    config.args = Some(self.args(&key, value)?)
+                }
+                ("unused-deps", "tool") => {
+                    config.tool = Some(self.choice(&key, value, UNUSED_DEPS_TOOLS)?)
+                }
+                ("miri", "args") => config.args = Some(self.args(&key, value)?),
+                ("bench", "threshold") => config.threshold = Some(self.percentage(&key, value)?),
+                ("bench", "baseline") => config.baseline = Some(self.string(&key, value)?),
+                ("target-check", "targets") => config.targets = Some(self.strings(&key, value)?),
+                ("no-std-check", "features") => config.features = self.strings(&key, value)?,
+                ("license", "allowlist") => config.allowlist = Some(self.string(&key, value)?),
+                ("geiger", "budget") => config.budget = Some(self.unsigned(&key, value)?),
+                ("coverage", "tool") => {
+                    config.tool = Some(self.choice(&key, value, COVERAGE_TOOLS)?)
+                }
+                ("coverage", "threshold") => config.threshold = Some(self.percentage(&key, value)?),
+                _ => return self.error(&key, format!("unknown setting for '{}' command", command)),
+            }
+        }
+        Ok(config)
+    }
+}
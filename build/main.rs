@@ -0,0 +1,1716 @@
+mod ci;
+mod config;
+mod script;
+mod toml;
+mod yaml;
+
+use ci::CI_VARS;
+use config::Config;
+use fs::File;
+use io::{BufRead, Read, Write};
+use path::{Path, PathBuf};
+use std::cmp::Ordering;
+use std::env::var_os;
+use std::process::{self, Command};
+use std::sync::mpsc;
+use std::{env, fmt, fs, io, path, thread, time};
+
+// Logs the steps of installation to stderr when `$CARGO_HUSKY_DEBUG` is set. Output of build
+// scripts is shown by `cargo test -vv`
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        if var_os("CARGO_HUSKY_DEBUG").is_some() {
+            eprintln!("cargo-husky: {}", format!($($arg)*));
+        }
+    };
+}
+
+enum Error {
+    GitDirNotFound,
+    BareRepository(PathBuf),
+    RepositoryNotFound(PathBuf),
+    TransitiveDependency(PathBuf),
+    HooksPathOutsideRepository(PathBuf),
+    Io(io::Error),
+    OutDirNotSet,
+    InvalidUserHooksDir(PathBuf),
+    InvalidHookCrate(PathBuf),
+    EmptyUserHook(PathBuf),
+    InvalidManifest(PathBuf, toml::ParseError),
+    InvalidConfig {
+        file: PathBuf,
+        key: String,
+        message: String,
+    },
+    GitCommandFailed(String, String),
+    InvalidHookScript(PathBuf, String),
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Error {
+        Error::Io(error)
+    }
+}
+
+impl fmt::Debug for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let msg = match self {
+            Error::GitDirNotFound => format!(
+                ".git directory was not found in '{}' or its parent directories",
+                Path::new(&var_os("OUT_DIR").unwrap_or_default()).display(),
+            ),
+            Error::BareRepository(path) => {
+                format!("Repository {:?} is bare and has no working tree", path)
+            }
+            Error::RepositoryNotFound(path) => {
+                format!(
+                    "Repository configured by 'repository' was not found at {:?}",
+                    path
+                )
+            }
+            Error::TransitiveDependency(path) => format!(
+                "No package in {:?} depends on cargo-husky directly. It is built as a dependency of a dependency",
+                path
+            ),
+            Error::HooksPathOutsideRepository(path) => format!(
+                "core.hooksPath {:?} is outside the repository. Git only runs hooks in it, so hooks in .git/hooks would be inert",
+                path
+            ),
+            Error::Io(inner) => format!("IO error: {}", inner),
+            Error::OutDirNotSet => {
+                "$OUT_DIR is not set. Build script must be run by cargo".to_string()
+            }
+            Error::InvalidUserHooksDir(path) => {
+                format!("User hooks directory is not found or no executable file is found in '{:?}'. Did you forget to make a hook script executable?", path)
+            }
+            Error::InvalidHookCrate(path) => {
+                format!("Hook crate is not found or it has no binary named after a hook such as 'pre-commit' in '{:?}'", path)
+            }
+            Error::EmptyUserHook(path) => format!("User hook script is empty: {:?}", path),
+            Error::InvalidManifest(path, err) => format!("Could not parse {:?}: {}", path, err),
+            Error::InvalidConfig { file, key, message } => {
+                format!("Invalid configuration '{}' in {:?}: {}", key, file, message)
+            }
+            Error::GitCommandFailed(cmd, stderr) => format!("`{}` failed: {}", cmd, stderr),
+            Error::InvalidHookScript(path, stderr) => format!(
+                "Hook script {:?} was not written since it has a syntax error: {}",
+                path, stderr
+            ),
+        };
+        write!(f, "{}", msg)
+    }
+}
+
+// WSL is detected by the variable set in WSL sessions or the kernel release
+fn is_wsl() -> bool {
+    var_os("WSL_DISTRO_NAME").is_some()
+        || fs::read_to_string("/proc/sys/kernel/osrelease")
+            .is_ok_and(|r| r.to_ascii_lowercase().contains("microsoft"))
+}
+
+// Translates a path written by Git on the other side of WSL. When the repository is shared between
+// Windows and WSL, `.git` files may contain `C:/path` in WSL or `/mnt/c/path` on Windows
+fn host_path(path: &str) -> PathBuf {
+    let bytes = path.as_bytes();
+    if cfg!(target_os = "windows") {
+        if let Some(rest) = path.strip_prefix("/mnt/") {
+            let mut chars = rest.chars();
+            if let (Some(drive), Some('/')) = (chars.next(), chars.next()) {
+                if drive.is_ascii_alphabetic() {
+                    return PathBuf::from(format!(
+                        "{}:/{}",
+                        drive.to_ascii_uppercase(),
+                        &rest[2..]
+                    ));
+                }
+            }
+        }
+    } else if bytes.len() >= 3
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && (bytes[2] == b'/' || bytes[2] == b'\\')
+        && is_wsl()
+    {
+        return PathBuf::from(format!(
+            "/mnt/{}/{}",
+            (bytes[0] as char).to_ascii_lowercase(),
+            path[3..].replace('\\', "/")
+        ));
+    }
+    PathBuf::from(path)
+}
+
+// `fs::canonicalize` on Windows returns verbatim paths such as `\\?\C:\path` which Git and sh do
+// not understand
+#[cfg(target_os = "windows")]
+fn normalize_path(path: PathBuf) -> PathBuf {
+    match path.to_str() {
+        Some(s) if s.starts_with(r"\\?\UNC\") => PathBuf::from(format!(r"\\{}", &s[8..])),
+        Some(s) if s.starts_with(r"\\?\") => PathBuf::from(&s[4..]),
+        _ => path,
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn normalize_path(path: PathBuf) -> PathBuf {
+    path
+}
+
+// Repository where the crate is built
+#[derive(Debug)]
+struct Repository {
+    // Root of the working tree where Cargo.toml and `.cargo-husky` directory are put
+    root: PathBuf,
+    // Git directory shared by all worktrees. Hooks are put in `hooks` directory of it
+    common_dir: PathBuf,
+}
+
+// Absolute path in the environment variable. Relative paths such as `GIT_DIR=.git` set by Git
+// running hooks are ignored since build scripts do not run in the directory where cargo was run
+fn absolute_path_var(name: &str) -> Option<PathBuf> {
+    var_os(name)
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute() && p.is_dir())
+}
+
+fn resolve_gitdir() -> Result<Repository> {
+    // Paths are kept as `OsString` since they may not be valid UTF-8
+    let mut dir = PathBuf::from(var_os("OUT_DIR").ok_or(Error::OutDirNotSet)?);
+    if !dir.has_root() {
+        dir = normalize_path(fs::canonicalize(dir)?);
+    }
+    debug!("Searching Git directory from $OUT_DIR {:?}", dir);
+    // When the target directory is outside the repository such as shared `$CARGO_TARGET_DIR`, the
+    // repository is searched from the directory where cargo was run. `$CARGO_MANIFEST_DIR` cannot be
+    // used since it is the directory of cargo-husky itself
+    let found = match find_repository(dir) {
+        Err(Error::GitDirNotFound) => match absolute_path_var("PWD") {
+            Some(pwd) => find_repository(pwd),
+            None => Err(Error::GitDirNotFound),
+        },
+        // Nested repository containing the directory where cargo was run is nearer to the crate than
+        // the repository containing the target directory
+        Ok(repo) => match absolute_path_var("PWD") {
+            Some(ref pwd) if pwd.starts_with(&repo.root) => match find_repository(pwd.clone()) {
+                Ok(nested) if nested.root.starts_with(&repo.root) => Ok(nested),
+                _ => Ok(repo),
+            },
+            _ => Ok(repo),
+        },
+        found => found,
+    };
+    match found {
+        Ok(ref repo) => debug!("Found repository {:?}", repo),
+        Err(ref err) => debug!("Repository was not found: {:?}", err),
+    }
+
+    // `$GIT_DIR` and `$GIT_COMMON_DIR` set by CI wrappers or IDEs are preferred to the Git
+    // directory found from the target directory
+    let git_dir = match absolute_path_var("GIT_DIR") {
+        Some(dir) => dir,
+        None => {
+            let mut repo = found?;
+            if let Some(common_dir) = absolute_path_var("GIT_COMMON_DIR") {
+                debug!("Using $GIT_COMMON_DIR {:?}", common_dir);
+                repo.common_dir = common_dir;
+            }
+            return Ok(repo);
+        }
+    };
+    let root = match (absolute_path_var("GIT_WORK_TREE"), found) {
+        (Some(root), _) => root,
+        (None, Ok(repo)) => repo.root,
+        (None, Err(_)) => match git_dir.parent() {
+            Some(parent) => parent.to_path_buf(),
+            None => return Err(Error::GitDirNotFound),
+        },
+    };
+    let common_dir = match absolute_path_var("GIT_COMMON_DIR") {
+        Some(dir) => dir,
+        None => common_dir(git_dir)?,
+    };
+    let repo = Repository { root, common_dir };
+    debug!("Using repository {:?} configured by $GIT_DIR", repo);
+    Ok(repo)
+}
+
+fn find_repository(dir: PathBuf) -> Result<Repository> {
+    match find_gitdir(dir.clone()) {
+        // The target directory may be a symlink or a directory junction to the repository. Its
+        // real path is searched as well
+        Err(Error::GitDirNotFound) => match fs::canonicalize(&dir).map(normalize_path) {
+            Ok(real) if real != dir => find_gitdir(real),
+            _ => Err(Error::GitDirNotFound),
+        },
+        found => found,
+    }
+}
+
+fn find_gitdir(mut dir: PathBuf) -> Result<Repository> {
+    loop {
+        let gitdir = dir.join(".git");
+        if gitdir.is_dir() {
+            return Ok(Repository {
+                root: dir,
+                common_dir: gitdir,
+            });
+        }
+        if gitdir.is_file() {
+            let mut buf = String::new();
+            File::open(gitdir)?.read_to_string(&mut buf)?;
+            let newlines: &[_] = &['\n', '\r'];
+            let gitdir = buf.trim_end_matches(newlines);
+            let gitdir = host_path(gitdir.strip_prefix("gitdir: ").unwrap_or(gitdir));
+            // Submodules have relative path such as `../.git/modules/foo` from the `.git` file
+            let gitdir = dir.join(gitdir);
+            debug!("Git directory in .git file: {:?}", gitdir);
+            if !gitdir.is_dir() {
+                return Err(Error::GitDirNotFound);
+            }
+            return Ok(Repository {
+                root: dir,
+                common_dir: common_dir(gitdir)?,
+            });
+        }
+        // Bare repository such as a mirror has Git directory files in its root
+        if dir.join("HEAD").is_file() && dir.join("objects").is_dir() && dir.join("refs").is_dir() {
+            return Err(Error::BareRepository(dir));
+        }
+        if !dir.pop() {
+            return Err(Error::GitDirNotFound);
+        }
+    }
+}
+
+// Git directory of a linked worktree such as `.git/worktrees/<name>` has `commondir` file which
+// points at the Git directory of the main worktree. Hooks in the directory are shared by worktrees
+fn common_dir(gitdir: PathBuf) -> Result<PathBuf> {
+    let commondir = match fs::read_to_string(gitdir.join("commondir")) {
+        Ok(content) => content,
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(gitdir),
+        Err(err) => return Err(Error::Io(err)),
+    };
+    let dir = gitdir.join(host_path(commondir.trim_end_matches(['\n', '\r'])));
+    Ok(normalize_path(fs::canonicalize(dir)?))
+}
+
+// `$CARGO_HUSKY_FORCE` regenerates hooks set by cargo-husky after they were edited
+fn force_install() -> bool {
+    var_os("CARGO_HUSKY_FORCE").is_some()
+}
+
+// This function returns true when
+//   - the hook was generated by the same or newer version of cargo-husky
+//   - someone else had already put another hook script
+// For safety, cargo-husky does nothing on case2 also. Hooks of newer version are not downgraded
+// unless `$CARGO_HUSKY_FORCE` is set
+fn hook_already_exists(hook: &Path) -> bool {
+    let f = match File::open(hook) {
+        Ok(f) => f,
+        Err(..) => return false,
+    };
+
+    let ver_line = match io::BufReader::new(f).lines().nth(2) {
+        None => return true, // Less than 2 lines. The hook script seemed to be generated by someone else
+        Some(Err(..)) => return false, // Failed to read entry. Re-generate anyway
+        Some(Ok(line)) => line,
+    };
+
+    if !ver_line.contains("This hook was set by cargo-husky") {
+        // The hook script was generated by someone else.
+        true
+    } else if force_install() {
+        false // Regenerated even if it is up to date
+    } else {
+        // Hooks installed by newer version, for example on another branch, are not downgraded
+        match marker_version(&ver_line).and_then(|v| compare_versions(v, env!("CARGO_PKG_VERSION")))
+        {
+            Some(ord) => ord != Ordering::Less,
+            None => false,
+        }
+    }
+}
+
+// Version in the comment `This hook was set by cargo-husky v1.2.3: <homepage>`
+fn marker_version(line: &str) -> Option<&str> {
+    let rest = line.split("This hook was set by cargo-husky v").nth(1)?;
+    rest.split(':').next()
+}
+
+// Returns the version of cargo-husky which installed the hook when it is newer than this version
+fn newer_installed_version(hook: &Path) -> Option<String> {
+    if force_install() {
+        return None;
+    }
+    let f = File::open(hook).ok()?;
+    let line = io::BufReader::new(f).lines().nth(2)?.ok()?;
+    let version = marker_version(&line)?;
+    match compare_versions(version, env!("CARGO_PKG_VERSION")) {
+        Some(Ordering::Greater) => Some(version.to_string()),
+        _ => None,
+    }
+}
+
+// Splits the version into major, minor and patch numbers and pre-release identifiers. Build
+// metadata is ignored
+fn parse_version(version: &str) -> Option<([u64; 3], Vec<&str>)> {
+    let version = version.split('+').next()?;
+    let (core, pre) = match version.find('-') {
+        Some(i) => (&version[..i], version[i + 1..].split('.').collect()),
+        None => (version, vec![]),
+    };
+    let mut numbers = [0; 3];
+    let mut parts = core.split('.');
+    for n in &mut numbers {
+        *n = parts.next()?.parse().ok()?;
+    }
+    if parts.next().is_some() {
+        return None;
+    }
+    Some((numbers, pre))
+}
+
+// Compares the versions by the precedence of semantic versioning. `None` is returned when either
+// of them cannot be parsed
+fn compare_versions(a: &str, b: &str) -> Option<Ordering> {
+    let (a_numbers, a_pre) = parse_version(a)?;
+    let (b_numbers, b_pre) = parse_version(b)?;
+    let pre = match (a_pre.is_empty(), b_pre.is_empty()) {
+        (true, true) => Ordering::Equal,
+        // Pre-release has lower precedence than the release
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+        (false, false) => a_pre
+            .iter()
+            .zip(&b_pre)
+            .map(|(x, y)| match (x.parse::<u64>(), y.parse::<u64>()) {
+                (Ok(x), Ok(y)) => x.cmp(&y),
+                (Ok(..), Err(..)) => Ordering::Less,
+                (Err(..), Ok(..)) => Ordering::Greater,
+                (Err(..), Err(..)) => x.cmp(y),
+            })
+            .find(|o| *o != Ordering::Equal)
+            .unwrap_or_else(|| a_pre.len().cmp(&b_pre.len())),
+    };
+    Some(a_numbers.cmp(&b_numbers).then(pre))
+}
+
+#[cfg(target_os = "windows")]
+fn create_executable_file(path: &Path, _hook: &Path) -> io::Result<File> {
+    File::create(path)
+}
+
+#[cfg(not(target_os = "windows"))]
+fn create_executable_file(path: &Path, hook: &Path) -> io::Result<File> {
+    use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+    let file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o755)
+        .open(path)?;
+
+    // The mode is not applied to the existing file and is masked by umask. Some filesystems such as
+    // exFAT and network mounts ignore it completely. Git silently skips non-executable hooks
+    if file.metadata()?.permissions().mode() & 0o111 != 0o111 {
+        file.set_permissions(fs::Permissions::from_mode(0o755))?;
+        if file.metadata()?.permissions().mode() & 0o111 == 0 {
+            println!(
+                "cargo:warning=Hook script {:?} could not be made executable. Git does not run it until its executable bit is set. The filesystem may not support executable bits",
+                hook
+            );
+        }
+    }
+    Ok(file)
+}
+
+// `$CARGO_HUSKY_DRY_RUN` prints the files which would be written, moved or removed to stderr
+// instead of modifying the repository
+fn dry_run() -> bool {
+    var_os("CARGO_HUSKY_DRY_RUN").is_some()
+}
+
+fn rename_file(from: &Path, to: &Path) -> Result<()> {
+    if dry_run() {
+        eprintln!("cargo-husky: Would move {:?} to {:?}", from, to);
+        return Ok(());
+    }
+    fs::rename(from, to)?;
+    Ok(())
+}
+
+fn remove_file(path: &Path) -> Result<()> {
+    if dry_run() {
+        eprintln!("cargo-husky: Would remove {:?}", path);
+        return Ok(());
+    }
+    fs::remove_file(path)?;
+    Ok(())
+}
+
+// Writes the file by renaming the temporary file written in the same directory. Build scripts of
+// several workspace members may install the same hook in parallel. Git never sees a truncated hook
+fn write_atomically(path: &Path, content: &[u8], executable: bool) -> Result<()> {
+    if dry_run() {
+        eprintln!(
+            "cargo-husky: Would write {:?}:\n{}",
+            path,
+            String::from_utf8_lossy(content)
+        );
+        return Ok(());
+    }
+    // The target of the symlink is updated instead of replacing the symlink itself
+    let resolved = match fs::symlink_metadata(path) {
+        Ok(m) if m.file_type().is_symlink() => fs::canonicalize(path).ok(),
+        _ => None,
+    };
+    let path = resolved.as_deref().unwrap_or(path);
+    let mut name = std::ffi::OsString::from(".");
+    name.push(path.file_name().unwrap());
+    name.push(format!(".{}.tmp", process::id()));
+    let tmp = path.with_file_name(name);
+    let file = if executable {
+        create_executable_file(&tmp, path)
+    } else {
+        File::create(&tmp)
+    };
+    let written = file
+        .and_then(|mut f| f.write_all(content))
+        .and_then(|_| fs::rename(&tmp, path));
+    if written.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    Ok(written?)
+}
+
+// Lock file in the Git directory which serializes installations by concurrent build scripts. It is
+// removed on drop. The lock left by a killed build script is removed after it gets stale. No lock is
+// taken on dry run
+struct InstallLock(Option<HeldLock>);
+
+// While the lock is held, a thread updates the modified time of the lock file so that the lock does
+// not get stale however long the installation takes. Dropping `stop` stops the thread
+struct HeldLock {
+    path: PathBuf,
+    stop: mpsc::Sender<()>,
+    refresher: thread::JoinHandle<()>,
+}
+
+const LOCK_FILE: &str = "cargo-husky.lock";
+const STALE_LOCK: time::Duration = time::Duration::from_secs(10);
+
+fn is_stale(path: &Path) -> bool {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.elapsed().ok())
+        .is_some_and(|elapsed| elapsed > STALE_LOCK)
+}
+
+impl InstallLock {
+    fn acquire(repo: &Repository) -> Result<InstallLock> {
+        let path = repo.common_dir.join(LOCK_FILE);
+        if dry_run() {
+            return Ok(InstallLock(None));
+        }
+        loop {
+            match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+            {
+                Ok(_) => return Ok(InstallLock(Some(HeldLock::refresh(path)))),
+                Err(ref err) if err.kind() == io::ErrorKind::AlreadyExists => {}
+                Err(err) => return Err(Error::Io(err)),
+            }
+            if is_stale(&path) {
+                break_stale_lock(&path);
+            } else {
+                thread::sleep(time::Duration::from_millis(50));
+            }
+        }
+    }
+}
+
+// Removing the stale lock directly could remove the lock which another build script has just
+// taken after breaking the same stale lock. The lock is atomically renamed to a unique name first,
+// so only one build script gets it. When the renamed lock is not stale, it was taken by another
+// build script in the meantime and it is put back unless a new lock was already taken
+fn break_stale_lock(path: &Path) {
+    let nanos = time::SystemTime::now()
+        .duration_since(time::UNIX_EPOCH)
+        .map_or(0, |d| d.subsec_nanos());
+    let broken = path.with_file_name(format!("{}.{}-{}.stale", LOCK_FILE, process::id(), nanos));
+    if fs::rename(path, &broken).is_err() {
+        return;
+    }
+    if !is_stale(&broken) {
+        let _ = fs::hard_link(&broken, path);
+    }
+    let _ = fs::remove_file(&broken);
+}
+
+impl HeldLock {
+    fn refresh(path: PathBuf) -> HeldLock {
+        let (stop, stopped) = mpsc::channel::<()>();
+        let refreshed = path.clone();
+        let refresher = thread::spawn(move || {
+            while let Err(mpsc::RecvTimeoutError::Timeout) = stopped.recv_timeout(STALE_LOCK / 4) {
+                let _ = File::options()
+                    .write(true)
+                    .open(&refreshed)
+                    .and_then(|f| f.set_modified(time::SystemTime::now()));
+            }
+        });
+        HeldLock {
+            path,
+            stop,
+            refresher,
+        }
+    }
+}
+
+impl Drop for InstallLock {
+    fn drop(&mut self) {
+        if let Some(held) = self.0.take() {
+            drop(held.stop);
+            let _ = held.refresher.join();
+            let _ = fs::remove_file(&held.path);
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn create_hooks_dir(path: &Path) -> io::Result<()> {
+    fs::create_dir_all(path)
+}
+
+#[cfg(not(target_os = "windows"))]
+fn create_hooks_dir(path: &Path) -> io::Result<()> {
+    use std::os::unix::fs::DirBuilderExt;
+
+    fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o755)
+        .create(path)
+}
+
+// Runs all executable files in `<hook>.d` directory in order of their names. Hooks which read
+// stdin are given the same input for each file
+const DISPATCHER_SCRIPT: &str = r#"#!/bin/sh
+#
+# This hook was set by cargo-husky v{version}: {homepage}
+# Dispatcher which runs all executable files in {hook}.d directory
+#
+
+set -e
+{read_stdin}for f in "$(dirname "$0")/{hook}.d"/*; do
+    if [ -f "$f" ] && [ -x "$f" ]; then
+        {run}
+    fi
+done
+"#;
+
+// Hooks which are given input from git via stdin
+const STDIN_HOOKS: &[&str] = &["pre-push", "post-rewrite"];
+
+fn set_by_cargo_husky(hook: &Path) -> bool {
+    match File::open(hook) {
+        Ok(f) => match io::BufReader::new(f).lines().nth(2) {
+            Some(Ok(line)) => line.contains("This hook was set by cargo-husky"),
+            _ => false,
+        },
+        Err(..) => false,
+    }
+}
+
+// Hash of the features and the configuration in the comment of the generated hook script
+fn config_hash(script: &str) -> Option<&str> {
+    script
+        .lines()
+        .find_map(|line| line.strip_prefix(script::CONFIG_HASH))
+}
+
+// Installs the dispatcher as the hook and returns the path where the hook script of cargo-husky
+// should be put. A hook script put by other tool is moved into `<hook>.d` directory so that both
+// run
+fn install_dispatcher(hooks_dir: &Path, hook: &str) -> Result<PathBuf> {
+    let dispatcher = hooks_dir.join(hook);
+    let dir = hooks_dir.join(format!("{}.d", hook));
+    if !dry_run() {
+        fs::create_dir_all(&dir)?;
+    }
+
+    let (read_stdin, run) = if STDIN_HOOKS.contains(&hook) {
+        (
+            "input=\"$(cat)\"\n",
+            // `$(cat)` drops trailing newline. Nothing is passed when stdin was empty
+            "{ [ -z \"$input\" ] || printf '%s\\n' \"$input\"; } | \"$f\" \"$@\"",
+        )
+    } else {
+        ("", "\"$f\" \"$@\"")
+    };
+    let script = DISPATCHER_SCRIPT
+        .replace("{version}", env!("CARGO_PKG_VERSION"))
+        .replace("{homepage}", env!("CARGO_PKG_HOMEPAGE"))
+        .replace("{hook}", hook)
+        .replace("{read_stdin}", read_stdin)
+        .replace("{run}", run)
+        .replace("\r\n", "\n");
+
+    if fs::read_to_string(&dispatcher).ok().as_ref() != Some(&script)
+        && newer_installed_version(&dispatcher).is_none()
+    {
+        if dispatcher.exists() && !set_by_cargo_husky(&dispatcher) {
+            let moved = dir.join(hook);
+            if moved.exists() {
+                // For safety, the hook script is not overwritten when it cannot be moved
+                return Ok(dir.join("cargo-husky"));
+            }
+            rename_file(&dispatcher, &moved)?;
+        }
+        write_atomically(&dispatcher, script.as_bytes(), true)?;
+    }
+
+    Ok(dir.join("cargo-husky"))
+}
+
+// Result of installing a hook. Only hooks owned by cargo-husky are recorded in the manifest
+#[derive(PartialEq)]
+enum Installed {
+    // The hook script was written
+    Written,
+    // The hook script set by cargo-husky was already up to date or newer
+    Current,
+    // The hook script belongs to other tools or the user and was left as it is
+    NotOurs,
+}
+
+fn install_hook(hooks_dir: &Path, hook: &str, config: &Config) -> Result<Installed> {
+    let hook_path = if cfg!(feature = "hook-dispatcher") {
+        install_dispatcher(hooks_dir, hook)?
+    } else {
+        hooks_dir.join(hook)
+    };
+    // The hook script put by other tool is preserved as `<hook>.local` and run by generated hook
+    // Hooks installed by husky (npm) are replaced with `replace-npm-husky-hooks` feature
+    let replaced = cfg!(feature = "replace-npm-husky-hooks") && is_npm_husky_hook(&hook_path);
+    let mut chained = false; // The hook script is still there on dry run
+    if cfg!(feature = "chain-existing-hooks")
+        && !replaced
+        && hook_path.exists()
+        && !set_by_cargo_husky(&hook_path)
+    {
+        let local = hooks_dir.join(format!("{}.local", hook));
+        if !local.exists() {
+            rename_file(&hook_path, &local)?;
+            chained = true;
+        }
+    }
+    if cfg!(feature = "embed-in-existing-hooks")
+        && !chained
+        && !replaced
+        && hook_path.exists()
+        && !set_by_cargo_husky(&hook_path)
+    {
+        return embed_hook(&hook_path, hook, config);
+    }
+    // Hook scripts put by other tools are only overwritten with `overwrite-foreign-hooks` feature
+    let foreign = (cfg!(feature = "overwrite-foreign-hooks") || replaced)
+        && !chained
+        && hook_path.exists()
+        && !set_by_cargo_husky(&hook_path);
+    if !foreign && !chained && is_npm_husky_hook(&hook_path) {
+        println!(
+            "cargo:warning=Hook script {:?} was installed by husky (npm). Enable `replace-npm-husky-hooks` feature to replace it or `chain-existing-hooks` feature to run it from the hook of cargo-husky",
+            hook_path
+        );
+        return Ok(Installed::NotOurs);
+    }
+    if let Some(version) = newer_installed_version(&hook_path) {
+        println!(
+            "cargo:warning=Hook script {:?} was installed by newer cargo-husky v{}. It was not downgraded to v{}. Set $CARGO_HUSKY_FORCE to regenerate it",
+            hook_path,
+            version,
+            env!("CARGO_PKG_VERSION")
+        );
+        return Ok(Installed::Current);
+    }
+    let mut script = vec![];
+    script::write_script(&mut script, hook, config)?;
+    let script = String::from_utf8_lossy(&script).into_owned();
+    let existing = fs::read_to_string(&hook_path).unwrap_or_default();
+    let reconfigured =
+        set_by_cargo_husky(&hook_path) && config_hash(&existing) != config_hash(&script);
+    debug!(
+        "Hook {:?}: exists={} set_by_cargo_husky={} already_exists={} foreign={} reconfigured={}",
+        hook_path,
+        hook_path.exists(),
+        set_by_cargo_husky(&hook_path),
+        hook_already_exists(&hook_path),
+        foreign,
+        reconfigured
+    );
+    debug!("Generated {} hook script:\n{}", hook, script);
+    if foreign || chained || reconfigured || !hook_already_exists(&hook_path) {
+        // Changes made to the generated hook by hand are not lost by updating cargo-husky
+        if !foreign && !chained && !force_install() && script::is_modified(&existing) {
+            println!(
+                "cargo:warning=Hook script {:?} was modified after cargo-husky generated it. It was not updated. Remove it or set $CARGO_HUSKY_FORCE to regenerate it",
+                hook_path
+            );
+            return Ok(Installed::NotOurs);
+        }
+        check_syntax(&hook_path, script.as_bytes())?;
+        if !chained {
+            backup_hook(&hook_path, script.as_bytes())?;
+        }
+        write_atomically(&hook_path, script.as_bytes(), true)?;
+        return Ok(Installed::Written);
+    }
+    if hook_path.exists() && !set_by_cargo_husky(&hook_path) {
+        println!(
+            "cargo:warning=Hook script {:?} was put by other tool. cargo-husky did not install {} hook. Enable `chain-existing-hooks`, `embed-in-existing-hooks` or `overwrite-foreign-hooks` feature to install it",
+            hook_path, hook
+        );
+        return Ok(Installed::NotOurs);
+    }
+    Ok(Installed::Current)
+}
+
+// Hook scripts generated by husky of npm. husky v4 puts them in `.git/hooks` with `# husky` header
+// and they source `husky.sh`. husky v9 generates stubs sourcing `h` in `core.hooksPath`
+fn is_npm_husky_hook(hook: &Path) -> bool {
+    let content = match fs::read_to_string(hook) {
+        Ok(content) => content,
+        Err(..) => return false,
+    };
+    content.lines().any(|line| {
+        let line = line.trim();
+        line == "# husky"
+            || (line.starts_with(". ") && line.ends_with("/husky.sh\""))
+            || line == ". \"$(dirname \"$0\")/h\""
+    })
+}
+
+// Returns the shell compatible with sh which runs the script. Scripts without shebang are run by sh
+fn script_shell(content: &str) -> Option<&'static str> {
+    match content.lines().next() {
+        Some(line) if line.starts_with("#!") => {
+            let interpreter = line.split_whitespace().last().unwrap_or("");
+            let interpreter = interpreter.rsplit('/').next().unwrap_or("");
+            ["sh", "bash", "dash", "ksh", "zsh"]
+                .iter()
+                .find(|&&s| s == interpreter)
+                .cloned()
+        }
+        _ => Some("sh"),
+    }
+}
+
+// Checks the script can be run by sh
+fn is_sh_script(content: &str) -> bool {
+    script_shell(content).is_some()
+}
+
+// Checks syntax of the hook script with `-n` option of its shell before writing it. A hook with a
+// syntax error would block every commit with a cryptic error. Scripts run by other interpreters
+// are not checked, nor are scripts whose shell is not installed
+fn check_syntax(hook: &Path, content: &[u8]) -> Result<()> {
+    let shell = match script_shell(&String::from_utf8_lossy(content)) {
+        Some(shell) => shell,
+        None => return Ok(()),
+    };
+    let mut child = match Command::new(shell)
+        .arg("-n")
+        .stdin(process::Stdio::piped())
+        .stdout(process::Stdio::null())
+        .stderr(process::Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(Error::Io(err)),
+    };
+    // The shell may exit before reading the whole script on a syntax error
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(content);
+    }
+    let output = child.wait_with_output()?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    Err(Error::InvalidHookScript(hook.to_owned(), stderr))
+}
+
+// Embeds commands of cargo-husky into the hook script put by other tool as a block delimited by
+// comments. Only the block is updated when cargo-husky is updated
+fn embed_hook(hook_path: &Path, hook: &str, config: &Config) -> Result<Installed> {
+    let content = fs::read_to_string(hook_path)?;
+    if !is_sh_script(&content) {
+        println!(
+            "cargo:warning=Hook script {:?} is not a shell script. cargo-husky did not embed its commands",
+            hook_path
+        );
+        return Ok(Installed::NotOurs);
+    }
+
+    let mut block = vec![];
+    script::write_block(&mut block, hook, config)?;
+    let block = String::from_utf8_lossy(&block);
+    let version = format!(
+        "This hook was set by cargo-husky v{}:",
+        env!("CARGO_PKG_VERSION")
+    );
+    let updated = match (
+        content.find(script::BLOCK_BEGIN),
+        content.find(script::BLOCK_END),
+    ) {
+        (Some(begin), Some(end)) if begin < end => {
+            let embedded = &content[begin..end];
+            if embedded.contains(&version)
+                && config_hash(embedded) == config_hash(&block)
+                && !force_install()
+            {
+                return Ok(Installed::Current);
+            }
+            let end = end + script::BLOCK_END.len();
+            let end = content[end..]
+                .find('\n')
+                .map_or(content.len(), |i| end + i + 1);
+            format!("{}{}{}", &content[..begin], block, &content[end..])
+        }
+        _ if content.is_empty() || content.ends_with("\n\n") => format!("{}{}", content, block),
+        _ if content.ends_with('\n') => format!("{}\n{}", content, block),
+        _ => format!("{}\n\n{}", content, block),
+    };
+    check_syntax(hook_path, updated.as_bytes())?;
+    backup_hook(hook_path, updated.as_bytes())?;
+    write_atomically(hook_path, updated.as_bytes(), true)?;
+    Ok(Installed::Written)
+}
+
+// Copies the existing hook script to `<hook>.<unix time>.bak` before overwriting it with the new
+// content. The backup is not executable so that it is never run as a hook
+fn backup_hook(hook: &Path, new_content: &[u8]) -> Result<()> {
+    let content = match fs::read(hook) {
+        Ok(content) => content,
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(Error::Io(err)),
+    };
+    // Previous hooks in the tracked directory can be restored from the history of the repository
+    if content == new_content || cfg!(feature = "tracked-hooks-dir") {
+        return Ok(());
+    }
+    let secs = time::SystemTime::now()
+        .duration_since(time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let mut name = hook.file_name().unwrap().to_owned();
+    name.push(format!(".{}.bak", secs));
+    let backup = hook.with_file_name(name);
+    if dry_run() {
+        eprintln!("cargo-husky: Would back up {:?} to {:?}", hook, backup);
+        return Ok(());
+    }
+    fs::write(&backup, content)?;
+    println!(
+        "cargo:warning=Existing hook script {:?} was backed up to {:?}",
+        hook, backup
+    );
+    Ok(())
+}
+
+// Values of placeholders such as `{{hook_name}}` expanded in user hook scripts
+struct Placeholders {
+    crate_version: String,
+    manifest_dir: PathBuf,
+}
+
+impl Placeholders {
+    // `{{manifest_dir}}` is kept since the path may not be valid UTF-8. It is expanded by `write`
+    fn expand(&self, line: &str, hook: &str) -> String {
+        line.replace("{{crate_version}}", &self.crate_version)
+            .replace("{{hook_name}}", hook)
+    }
+
+    // Writes the line expanding `{{manifest_dir}}` to the bytes of the path
+    fn write(&self, content: &mut Vec<u8>, line: &str) {
+        let dir = shell_path(&self.manifest_dir);
+        for (i, part) in line.split("{{manifest_dir}}").enumerate() {
+            if i > 0 {
+                content.extend_from_slice(&dir);
+            }
+            content.extend_from_slice(part.as_bytes());
+        }
+        content.push(b'\n');
+    }
+}
+
+// `recorded` are the hooks recorded in the manifest by the previous installation
+fn install_user_hook(
+    src: &Path,
+    dst: &Path,
+    placeholders: &Placeholders,
+    recorded: &[String],
+) -> Result<Installed> {
+    let hook = src.file_name().unwrap();
+    let mut lines = {
+        let mut vec = vec![];
+        for line in io::BufReader::new(File::open(src)?).lines() {
+            vec.push(placeholders.expand(&line?, &hook.to_string_lossy()));
+        }
+        vec
+    };
+
+    if lines.is_empty() {
+        return Err(Error::EmptyUserHook(src.to_owned()));
+    }
+
+    // Insert cargo-husky package version information as comment. Scripts run by other interpreters
+    // such as python3 are copied as-is since the comment may not be valid in the language
+    if !lines[0].starts_with("#!") {
+        lines.insert(0, "#".to_string());
+    }
+    if is_sh_script(&lines[0]) {
+        lines.insert(1, "#".to_string());
+        lines.insert(
+            2,
+            format!(
+                "# This hook was set by cargo-husky v{}: {}",
+                env!("CARGO_PKG_VERSION"),
+                env!("CARGO_PKG_HOMEPAGE")
+            ),
+        );
+    }
+
+    let dst_file_path = dst.join(hook);
+
+    let mut content = vec![];
+    for line in lines {
+        placeholders.write(&mut content, &line);
+    }
+    if fs::read(&dst_file_path).ok().as_ref() == Some(&content) {
+        return Ok(Installed::Current);
+    }
+    if let Some(version) = newer_installed_version(&dst_file_path) {
+        println!(
+            "cargo:warning=Hook script {:?} was installed by newer cargo-husky v{}. It was not downgraded to v{}. Set $CARGO_HUSKY_FORCE to regenerate it",
+            dst_file_path,
+            version,
+            env!("CARGO_PKG_VERSION")
+        );
+        return Ok(Installed::Current);
+    }
+    // Copies of scripts run by other interpreters have no version comment. They were installed by
+    // cargo-husky when the manifest recorded them
+    let hook = hook.to_string_lossy();
+    let ours = set_by_cargo_husky(&dst_file_path) || recorded.iter().any(|h| *h == hook);
+    if dst_file_path.exists() && !ours {
+        println!(
+            "cargo:warning=Hook script {:?} was put by other tool. cargo-husky did not install user hook {}",
+            dst_file_path, hook
+        );
+        return Ok(Installed::NotOurs);
+    }
+    check_syntax(&dst_file_path, &content)?;
+    backup_hook(&dst_file_path, &content)?;
+    write_atomically(&dst_file_path, &content, true)?;
+
+    Ok(Installed::Written)
+}
+
+#[cfg(target_os = "windows")]
+fn is_executable_file(entry: &fs::DirEntry) -> bool {
+    match entry.file_type() {
+        Ok(ft) => ft.is_file(),
+        Err(..) => false,
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn is_executable_file(entry: &fs::DirEntry) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    let ft = match entry.file_type() {
+        Ok(ft) => ft,
+        Err(..) => return false,
+    };
+    if !ft.is_file() {
+        return false;
+    }
+    let md = match entry.metadata() {
+        Ok(md) => md,
+        Err(..) => return false,
+    };
+    let mode = md.permissions().mode();
+    mode & 0o555 == 0o555 // Check file is read and executable mode
+}
+
+// Bytes of the path written in hook scripts. sh of Git for Windows accepts `C:/path/to/dir` but
+// backslashes are regarded as escape characters
+#[cfg(target_os = "windows")]
+fn shell_path(path: &Path) -> Vec<u8> {
+    path.to_string_lossy().replace('\\', "/").into_bytes()
+}
+
+// Paths may not be valid UTF-8. Their bytes are written as-is
+#[cfg(not(target_os = "windows"))]
+fn shell_path(path: &Path) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+
+    path.as_os_str().as_bytes().to_vec()
+}
+
+fn install_user_hooks(repo: &Repository) -> Result<()> {
+    let user_hooks_dir = repo.root.join(".cargo-husky").join("hooks");
+    rerun_if_changed(&user_hooks_dir);
+
+    if !user_hooks_dir.is_dir() {
+        return Err(Error::InvalidUserHooksDir(user_hooks_dir));
+    }
+
+    let hook_paths = fs::read_dir(&user_hooks_dir)?
+        .filter_map(|e| e.ok().filter(is_executable_file).map(|e| e.path()))
+        .collect::<Vec<_>>();
+
+    if hook_paths.is_empty() {
+        return Err(Error::InvalidUserHooksDir(user_hooks_dir));
+    }
+
+    let placeholders = Placeholders {
+        crate_version: config::crate_version(&repo.root)?.unwrap_or_default(),
+        manifest_dir: repo.root.clone(),
+    };
+    let _lock = InstallLock::acquire(repo)?;
+    let hooks_dir = hooks_dir(repo)?;
+    let recorded = recorded_hooks(repo);
+    let mut hooks = vec![];
+    for path in hook_paths {
+        if install_user_hook(&path, &hooks_dir, &placeholders, &recorded)? != Installed::NotOurs {
+            hooks.push(path.file_name().unwrap().to_string_lossy().into_owned());
+        }
+    }
+
+    write_manifest(repo, &hooks_dir, &hooks)
+}
+
+// Hook script which runs the binary of the hook crate. The binary is built on the first run and
+// rebuilt when the crate is modified
+const RUST_HOOK_SCRIPT: &str = r#"#!/bin/sh
+#
+# This hook was set by cargo-husky v{version}: {homepage}
+# Runs '{hook}' binary of the hook crate in .cargo-husky directory
+#
+
+exec cargo run --quiet --manifest-path "$(git rev-parse --show-toplevel)/.cargo-husky/Cargo.toml" --bin '{hook}' -- "$@"
+"#;
+
+// Installs hooks which run binaries of the hook crate in `.cargo-husky` directory. Binaries named
+// after hooks such as `src/bin/pre-commit.rs` are run as the hooks
+fn install_rust_hooks(repo: &Repository) -> Result<()> {
+    let crate_dir = repo.root.join(".cargo-husky");
+    let mut bins = config::bin_names(&crate_dir.join("Cargo.toml"))?;
+    let bin_dir = crate_dir.join("src").join("bin");
+    rerun_if_changed(&bin_dir);
+    for &(hook, _) in HOOKS {
+        if bin_dir.join(format!("{}.rs", hook)).is_file()
+            || bin_dir.join(hook).join("main.rs").is_file()
+        {
+            bins.push(hook.to_string());
+        }
+    }
+
+    let hooks = HOOKS
+        .iter()
+        .map(|&(hook, _)| hook)
+        .filter(|hook| bins.iter().any(|b| b == hook))
+        .collect::<Vec<_>>();
+    if !crate_dir.join("Cargo.toml").is_file() || hooks.is_empty() {
+        return Err(Error::InvalidHookCrate(crate_dir));
+    }
+
+    let _lock = InstallLock::acquire(repo)?;
+    let hooks_dir = hooks_dir(repo)?;
+    for &hook in &hooks {
+        let hook_path = hooks_dir.join(hook);
+        if hook_already_exists(&hook_path) {
+            continue;
+        }
+        let script = RUST_HOOK_SCRIPT
+            .replace("{version}", env!("CARGO_PKG_VERSION"))
+            .replace("{homepage}", env!("CARGO_PKG_HOMEPAGE"))
+            .replace("{hook}", hook)
+            .replace("\r\n", "\n");
+        backup_hook(&hook_path, script.as_bytes())?;
+        write_atomically(&hook_path, script.as_bytes(), true)?;
+    }
+    let hooks = hooks.iter().map(|h| h.to_string()).collect::<Vec<_>>();
+    write_manifest(repo, &hooks_dir, &hooks)
+}
+
+// Directory tracked by the repository where hooks are put with `tracked-hooks-dir` feature
+const TRACKED_HOOKS_DIR: &str = ".githooks";
+
+// `core.hooksPath` configured by the user or other tools. Git does not run hooks in `.git/hooks`
+// when it is set. Relative path is relative to the root of the working tree
+fn configured_hooks_path(repo: &Repository) -> Option<PathBuf> {
+    let output = Command::new("git")
+        .args(["config", "--path", "--get", "core.hooksPath"])
+        .current_dir(&repo.root)
+        .output()
+        .ok()?;
+    let path = String::from_utf8(output.stdout).ok()?;
+    let path = path.trim_end_matches(['\n', '\r']);
+    if !output.status.success() || path.is_empty() {
+        return None;
+    }
+    Some(repo.root.join(host_path(path)))
+}
+
+// Resolves `.` and `..` in the path without accessing the filesystem. The path may not exist yet
+fn normalize_components(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            path::Component::CurDir => {}
+            path::Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+// Checks the path is in the working tree or the Git directory. Symlinks are resolved when the path
+// exists
+fn is_in_repository(path: &Path, repo: &Repository) -> bool {
+    let path = normalize_components(path);
+    let real = fs::canonicalize(&path).map(normalize_path).ok();
+    [&repo.root, &repo.common_dir].iter().any(|dir| {
+        path.starts_with(dir)
+            || match (&real, fs::canonicalize(dir).map(normalize_path)) {
+                (Some(real), Ok(dir)) => real.starts_with(dir),
+                _ => false,
+            }
+    })
+}
+
+// Directory where Git runs hooks of the repository. `core.hooksPath` outside the repository such as
+// a global one is shared by all repositories on the machine. Hooks of this repository are not put
+// there
+fn repository_hooks_dir(repo: &Repository) -> Result<PathBuf> {
+    match configured_hooks_path(repo) {
+        Some(dir) if !is_in_repository(&dir, repo) => Err(Error::HooksPathOutsideRepository(dir)),
+        Some(dir) => Ok(dir),
+        None => Ok(repo.common_dir.join("hooks")),
+    }
+}
+
+// Returns the directory to put hooks. With `tracked-hooks-dir` feature, hooks are put in the
+// directory tracked by the repository and Git is configured to use it by `core.hooksPath`
+fn hooks_dir(repo: &Repository) -> Result<PathBuf> {
+    if !cfg!(feature = "tracked-hooks-dir") {
+        // `.git/hooks` may be missing with some clone or template configurations
+        let dir = repository_hooks_dir(repo)?;
+        debug!("Hooks directory: {:?}", dir);
+        if !dry_run() {
+            create_hooks_dir(&dir)?;
+        }
+        return Ok(dir);
+    }
+    let root_dir = &repo.root;
+    let dir = root_dir.join(TRACKED_HOOKS_DIR);
+    debug!("Tracked hooks directory: {:?}", dir);
+    if !dry_run() {
+        create_hooks_dir(&dir)?;
+    }
+    run_git(root_dir, &["config", "core.hooksPath", TRACKED_HOOKS_DIR])?;
+    Ok(dir)
+}
+
+// Runs git command which changes the repository. It is only printed on dry run
+fn run_git(root_dir: &Path, args: &[&str]) -> Result<()> {
+    if dry_run() {
+        eprintln!("cargo-husky: Would run `git {}`", args.join(" "));
+        return Ok(());
+    }
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(root_dir)
+        .output()?;
+    if !output.status.success() {
+        return Err(Error::GitCommandFailed(
+            format!("git {}", args.join(" ")),
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    Ok(())
+}
+
+// File in the Git directory recording the hooks installed by cargo-husky
+const MANIFEST_FILE: &str = "cargo-husky.json";
+
+fn json_string(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn json_array(items: &[String]) -> String {
+    let items = items.iter().map(|s| json_string(s)).collect::<Vec<_>>();
+    format!("[{}]", items.join(", "))
+}
+
+// Hooks recorded in the manifest by the previous installation. The manifest is only written by
+// `write_manifest`, so its format is known
+fn recorded_hooks(repo: &Repository) -> Vec<String> {
+    let manifest = fs::read_to_string(repo.common_dir.join(MANIFEST_FILE)).unwrap_or_default();
+    let hooks = match manifest
+        .lines()
+        .find_map(|line| line.strip_prefix("  \"hooks\": ["))
+    {
+        Some(hooks) => hooks.trim_end_matches(']'),
+        None => return vec![],
+    };
+    hooks
+        .split(", ")
+        .filter_map(|s| s.strip_prefix('"')?.strip_suffix('"'))
+        .map(|s| s.replace("\\\"", "\"").replace("\\\\", "\\"))
+        .collect()
+}
+
+// Writes the manifest listing the installed hooks with the version and the features of
+// cargo-husky which installed them. Features are read from `$CARGO_FEATURE_*` set by cargo
+fn write_manifest(repo: &Repository, hooks_dir: &Path, hooks: &[String]) -> Result<()> {
+    let mut features = env::vars_os()
+        .filter_map(|(name, _)| {
+            let name = name.into_string().ok()?;
+            let feature = name.strip_prefix("CARGO_FEATURE_")?;
+            Some(feature.to_lowercase().replace('_', "-"))
+        })
+        .collect::<Vec<_>>();
+    features.sort();
+    let mut hooks = hooks.to_vec();
+    hooks.sort();
+    let manifest = format!(
+        "{{\n  \"version\": {},\n  \"features\": {},\n  \"hooksDir\": {},\n  \"hooks\": {}\n}}\n",
+        json_string(env!("CARGO_PKG_VERSION")),
+        json_array(&features),
+        // JSON strings cannot contain bytes which are not valid UTF-8
+        json_string(&String::from_utf8_lossy(&shell_path(hooks_dir))),
+        json_array(&hooks),
+    );
+    // The manifest is not rewritten when nothing changed. Otherwise its timestamp would make the
+    // build script run again on every build
+    let path = repo.common_dir.join(MANIFEST_FILE);
+    if fs::read_to_string(&path).ok().as_ref() != Some(&manifest) {
+        write_atomically(&path, manifest.as_bytes(), false)?;
+    }
+    // Self-healing hooks build cargo-husky when the manifest is missing. The build script must run
+    // again to install hooks in the case
+    if cfg!(feature = "self-healing-hooks") {
+        rerun_if_changed(&path);
+    }
+    Ok(())
+}
+
+// Client-side Git hooks which cargo-husky can generate. Each hook is installed when its feature or
+// `all-hooks` feature is enabled
+const HOOKS: &[(&str, bool)] = &[
+    ("pre-push", cfg!(feature = "prepush-hook")),
+    ("pre-commit", cfg!(feature = "precommit-hook")),
+    ("post-merge", cfg!(feature = "postmerge-hook")),
+    ("commit-msg", cfg!(feature = "commit-msg-hook")),
+    (
+        "prepare-commit-msg",
+        cfg!(feature = "prepare-commit-msg-hook"),
+    ),
+    ("post-checkout", cfg!(feature = "postcheckout-hook")),
+    ("pre-rebase", cfg!(feature = "prerebase-hook")),
+    ("post-commit", cfg!(feature = "postcommit-hook")),
+    ("pre-merge-commit", cfg!(feature = "premergecommit-hook")),
+    ("applypatch-msg", cfg!(feature = "applypatch-msg-hook")),
+    ("pre-applypatch", cfg!(feature = "preapplypatch-hook")),
+    ("post-applypatch", cfg!(feature = "postapplypatch-hook")),
+    ("post-rewrite", cfg!(feature = "postrewrite-hook")),
+];
+
+// Resolves the repository to install hooks and loads the configuration. `repository` setting
+// points to a nested repository relative to the one containing the crate
+fn configured_repository(mut repo: Repository) -> Result<(Repository, Config)> {
+    let config = Config::load(&repo.root)?;
+    if let Some(ref path) = config.repository {
+        let root = repo.root.join(path);
+        if !root.join(".git").exists() {
+            return Err(Error::RepositoryNotFound(root));
+        }
+        repo = find_gitdir(root)?;
+    }
+    Ok((repo, config))
+}
+
+// Checks the crate being developed depends on cargo-husky. When it is only a dependency of a
+// dependency, hooks should not be installed into the repository of the downstream crate. Manifests
+// from the directory where `cargo` was run up to the repository root are checked. When none of them
+// depends on cargo-husky, the crate may still be built with `--manifest-path`, so the other
+// manifests in the repository are also checked before skipping
+fn check_direct_dependency(repo: &Repository) -> Result<()> {
+    let start = absolute_path_var("PWD")
+        .filter(|dir| dir.starts_with(&repo.root))
+        .unwrap_or_else(|| repo.root.clone());
+    let mut transitive = false;
+    for dir in start
+        .ancestors()
+        .take_while(|dir| dir.starts_with(&repo.root))
+    {
+        match config::depends_on_cargo_husky(dir) {
+            Ok(Some(true)) => return Ok(()),
+            Ok(Some(false)) => transitive = true,
+            Ok(None) => {}
+            // Manifests of other crates should not fail the build. The check is only a heuristic
+            Err(e) => {
+                println!(
+                    "cargo:warning={:?}. Installing hooks without checking that cargo-husky is a direct dependency",
+                    e
+                );
+                return Ok(());
+            }
+        }
+    }
+    if transitive && !declared_in_directory(&repo.root, &repo.root) {
+        return Err(Error::TransitiveDependency(repo.root.clone()));
+    }
+    Ok(()) // Layout without Cargo.toml is not known. Hooks are installed as before
+}
+
+// Directories which do not contain manifests of the repository. Build outputs may contain packaged
+// crates and installed npm packages are not crates of the repository
+const SKIPPED_DIRS: &[&str] = &["target", "node_modules"];
+
+// Searches manifests depending on cargo-husky in the directory recursively. Hidden directories and
+// nested repositories such as submodules are skipped. Invalid manifests are regarded as not depending
+// on cargo-husky
+fn declared_in_directory(dir: &Path, root: &Path) -> bool {
+    if dir != root && dir.join(".git").exists() {
+        return false;
+    }
+    if dir.join("Cargo.toml").is_file() {
+        if let Ok(Some(true)) = config::depends_on_cargo_husky(dir) {
+            return true;
+        }
+    }
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return false,
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .any(|entry| {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            !name.starts_with('.')
+                && !SKIPPED_DIRS.contains(&name.as_ref())
+                && declared_in_directory(&entry.path(), root)
+        })
+}
+
+// Makes the build script run again when the file or directory read by it changes. Cargo runs the
+// build script on every build when a watched path does not exist. Such a path is watched through a
+// symlink to it in a directory under `$OUT_DIR` instead, so that creating the file later runs the
+// build script again
+fn rerun_if_changed(path: &Path) {
+    if path.exists() {
+        println!("cargo:rerun-if-changed={}", path.display());
+    } else if let Some(dir) = watch_missing_path(path) {
+        println!("cargo:rerun-if-changed={}", dir.display());
+    }
+}
+
+// Cargo scans a watched directory and follows symlinks in it. A dangling symlink is ignored until
+// the file it points to is created
+#[cfg(not(target_os = "windows"))]
+fn watch_missing_path(path: &Path) -> Option<PathBuf> {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::fs::symlink;
+
+    if !path.is_absolute() {
+        return None;
+    }
+    let dir = PathBuf::from(var_os("OUT_DIR")?).join("missing");
+    // Separators are replaced so that each path has its own symlink
+    let name = path
+        .as_os_str()
+        .as_bytes()
+        .iter()
+        .map(|&b| if b == b'/' { b'%' } else { b })
+        .collect::<Vec<_>>();
+    let link = dir.join(OsStr::from_bytes(&name));
+    if fs::symlink_metadata(&link).is_err() {
+        fs::create_dir_all(&dir).ok()?;
+        symlink(path, &link).ok()?;
+    }
+    Some(dir)
+}
+
+// Creating symlinks requires a privilege on Windows
+#[cfg(target_os = "windows")]
+fn watch_missing_path(_path: &Path) -> Option<PathBuf> {
+    None
+}
+
+fn install() -> Result<()> {
+    let repo = resolve_gitdir()?;
+    check_direct_dependency(&repo)?;
+    if cfg!(feature = "user-hooks") {
+        return install_user_hooks(&repo);
+    }
+    if cfg!(feature = "rust-hooks") {
+        return install_rust_hooks(&repo);
+    }
+    let (repo, config) = configured_repository(repo)?;
+    let _lock = InstallLock::acquire(&repo)?;
+    let hooks_dir = hooks_dir(&repo)?;
+    let mut hooks = vec![];
+    for &(hook, enabled) in HOOKS {
+        if enabled || cfg!(feature = "all-hooks") || config.has_hook(hook) {
+            let installed = install_hook(&hooks_dir, hook, &config)?;
+            if installed == Installed::NotOurs {
+                continue;
+            }
+            if installed == Installed::Written && var_os("CARGO_HUSKY_QUIET").is_none() {
+                // Developers should know their `.git/hooks` was modified
+                let commands = config.commands(hook);
+                println!(
+                    "cargo:warning=cargo-husky {} {} hook ({}) in {:?}",
+                    if dry_run() {
+                        "would install"
+                    } else {
+                        "installed"
+                    },
+                    hook,
+                    if commands.is_empty() {
+                        "no commands".to_string()
+                    } else {
+                        commands.join(", ")
+                    },
+                    hooks_dir
+                );
+            }
+            hooks.push(hook.to_string());
+        }
+    }
+    write_manifest(&repo, &hooks_dir, &hooks)
+}
+
+// Removes the hook scripts set by cargo-husky. Hook scripts put by other tools are never deleted.
+// Those moved aside by cargo-husky are restored and blocks embedded in them are removed
+fn uninstall_hook(hooks_dir: &Path, hook: &str) -> Result<()> {
+    let hook_path = hooks_dir.join(hook);
+    let dir = hooks_dir.join(format!("{}.d", hook));
+    let in_dispatcher = dir.join("cargo-husky");
+    if set_by_cargo_husky(&in_dispatcher) {
+        remove_file(&in_dispatcher)?;
+    }
+
+    // The dispatcher is kept while it still runs scripts put by other tools
+    let others = match fs::read_dir(&dir) {
+        Ok(entries) => entries
+            .filter_map(|e| e.ok())
+            .any(|e| e.file_name() != hook && e.file_name() != "cargo-husky"),
+        Err(..) => false,
+    };
+    if set_by_cargo_husky(&hook_path) && !others {
+        remove_file(&hook_path)?;
+        let local = hooks_dir.join(format!("{}.local", hook));
+        let moved = dir.join(hook);
+        for original in &[local, moved] {
+            if original.is_file() {
+                rename_file(original, &hook_path)?;
+                break;
+            }
+        }
+    } else if hook_path.is_file() && !set_by_cargo_husky(&hook_path) {
+        remove_embedded_block(&hook_path)?;
+    }
+
+    if !dry_run() && dir.is_dir() && fs::read_dir(&dir)?.next().is_none() {
+        fs::remove_dir(&dir)?;
+    }
+    Ok(())
+}
+
+// Removes the block embedded by `embed-in-existing-hooks` feature and the blank line separating it
+fn remove_embedded_block(hook_path: &Path) -> Result<()> {
+    let content = match fs::read_to_string(hook_path) {
+        Ok(content) => content,
+        Err(..) => return Ok(()), // Not a text file. Nothing was embedded
+    };
+    let (begin, end) = match (
+        content.find(script::BLOCK_BEGIN),
+        content.find(script::BLOCK_END),
+    ) {
+        (Some(begin), Some(end)) if begin < end => (begin, end + script::BLOCK_END.len()),
+        _ => return Ok(()),
+    };
+    let end = content[end..]
+        .find('\n')
+        .map_or(content.len(), |i| end + i + 1);
+    let before = content[..begin].trim_end_matches('\n');
+    let after = &content[end..];
+    let updated = match (before.is_empty(), after.is_empty()) {
+        (true, _) => after.to_string(),
+        (false, true) => format!("{}\n", before),
+        (false, false) => format!("{}\n\n{}", before, after),
+    };
+    write_atomically(hook_path, updated.as_bytes(), true)?;
+    Ok(())
+}
+
+fn uninstall() -> Result<()> {
+    let (repo, _) = configured_repository(resolve_gitdir()?)?;
+    let _lock = InstallLock::acquire(&repo)?;
+    // Hooks directory is not created nor configured on uninstalling
+    let hooks_dir = repository_hooks_dir(&repo)?;
+    if hooks_dir.is_dir() {
+        for &(hook, _) in HOOKS {
+            uninstall_hook(&hooks_dir, hook)?;
+        }
+    }
+    // `core.hooksPath` set by `tracked-hooks-dir` feature is unset unless hooks of other tools
+    // are still in the directory
+    let tracked = repo.root.join(TRACKED_HOOKS_DIR);
+    if cfg!(feature = "tracked-hooks-dir")
+        && hooks_dir == tracked
+        && fs::read_dir(&tracked).map_or(true, |mut entries| entries.next().is_none())
+    {
+        run_git(&repo.root, &["config", "--unset", "core.hooksPath"])?;
+    }
+    let manifest = repo.common_dir.join(MANIFEST_FILE);
+    if manifest.exists() {
+        remove_file(&manifest)?;
+    }
+    Ok(())
+}
+
+// Returns the variable which indicates the build is run in CI. `CI=false` is not regarded as CI
+fn detect_ci() -> Option<&'static str> {
+    CI_VARS.iter().cloned().find(|&name| match var_os(name) {
+        Some(value) => !value.is_empty() && value != "false" && value != "0",
+        None => false,
+    })
+}
+
+// `cargo package` and `cargo publish` of older cargo verify the packaged crate with the target
+// directory in `<target>/package/<crate>-<version>`. The outer target directory has CACHEDIR.TAG
+fn is_package_verification() -> bool {
+    let out_dir = match var_os("OUT_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => return false,
+    };
+    out_dir.ancestors().any(|dir| {
+        dir.file_name().is_some_and(|name| name == "package")
+            && dir
+                .parent()
+                .is_some_and(|target| target.join("CACHEDIR.TAG").is_file())
+    })
+}
+
+// `io::ErrorKind::ReadOnlyFilesystem` is not available on old Rust. EROFS is 30 on Linux and macOS
+fn is_readonly_error(err: &io::Error) -> bool {
+    err.kind() == io::ErrorKind::PermissionDenied || cfg!(unix) && err.raw_os_error() == Some(30)
+}
+
+fn main() -> Result<()> {
+    // Build script is run again when the variables are set or unset so that hooks are installed,
+    // removed or regenerated without cleaning the build
+    println!("cargo:rerun-if-env-changed=CARGO_HUSKY_DONT_INSTALL_HOOKS");
+    println!("cargo:rerun-if-env-changed=CARGO_HUSKY_UNINSTALL");
+    println!("cargo:rerun-if-env-changed=CARGO_HUSKY_FORCE");
+    println!("cargo:rerun-if-env-changed=CARGO_HUSKY_STRICT");
+    println!("cargo:rerun-if-env-changed=CARGO_HUSKY_DEBUG");
+    println!("cargo:rerun-if-env-changed=CARGO_HUSKY_DRY_RUN");
+    for name in CI_VARS {
+        println!("cargo:rerun-if-env-changed={}", name);
+    }
+
+    if var_os("CARGO_HUSKY_DONT_INSTALL_HOOKS").is_some() {
+        eprintln!("Warning: Found '$CARGO_HUSKY_DONT_INSTALL_HOOKS' in env, not doing anything!");
+        return Ok(());
+    }
+
+    // docs.rs and verification of packaged crates only check that the crate can be built
+    if var_os("DOCS_RS").is_some() || is_package_verification() {
+        eprintln!("Warning: Building documentation or verifying the package. Skipped installing Git hooks");
+        return Ok(());
+    }
+
+    if !cfg!(feature = "install-in-ci") {
+        if let Some(name) = detect_ci() {
+            eprintln!(
+                "Warning: Found '${}' in env. Skipped installing Git hooks in CI",
+                name
+            );
+            return Ok(());
+        }
+    }
+
+    let result = if var_os("CARGO_HUSKY_UNINSTALL").is_some() {
+        uninstall()
+    } else {
+        install()
+    };
+
+    // `$CARGO_HUSKY_STRICT` overrides features which decide whether errors fail the build
+    let strict = var_os("CARGO_HUSKY_STRICT").map(|v| v != "0");
+    let fail_on_readonly = strict.unwrap_or(cfg!(feature = "fail-on-readonly-hooks"));
+    let fail_on_error = strict.unwrap_or(!cfg!(feature = "warn-on-errors"));
+
+    match result {
+        // Crates built from a source tarball, a vendored copy or a bare repository have no working
+        // tree to install hooks. Repositories of downstream crates and hooks directories shared by
+        // other repositories are left as they are
+        Err(e @ Error::GitDirNotFound)
+        | Err(e @ Error::BareRepository(_))
+        | Err(e @ Error::TransitiveDependency(_))
+        | Err(e @ Error::HooksPathOutsideRepository(_)) => {
+            // #2
+            eprintln!("Warning: {:?}", e);
+            println!("cargo:warning={:?}. Skipped installing Git hooks", e);
+            Ok(())
+        }
+        // Hooks directory may be read-only in CI caches or sandboxed builds
+        Err(Error::Io(err)) if !fail_on_readonly && is_readonly_error(&err) => {
+            println!(
+                "cargo:warning=Could not write Git hooks: {}. Skipped installing Git hooks",
+                err
+            );
+            Ok(())
+        }
+        // `fail-on-readonly-hooks` is respected even with `warn-on-errors`
+        Err(Error::Io(err)) if is_readonly_error(&err) => Err(Error::Io(err)),
+        Err(e) if !fail_on_error => {
+            println!(
+                "cargo:warning=Could not install Git hooks: {:?}. Skipped installing Git hooks",
+                e
+            );
+            Ok(())
+        }
+        otherwise => otherwise,
+    }
+}
@@ -0,0 +1,913 @@
+use config::Config;
+use std::path::{Path, PathBuf};
+use std::{env, io, path};
+use Result;
+
+// Commands which can be run in generated hook scripts with whether each is enabled by features and
+// hooks which run it by default. Empty hooks means all hooks. They are run in this order by default.
+const COMMANDS: &[(&str, bool, &[&str])] = &[
+    ("test", cfg!(feature = "run-cargo-test"), &[]),
+    ("check", cfg!(feature = "run-cargo-check"), &[]),
+    ("clippy", cfg!(feature = "run-cargo-clippy"), &[]),
+    ("fmt", cfg!(feature = "run-cargo-fmt"), &[]),
+    ("audit", cfg!(feature = "run-cargo-audit"), &[]),
+    ("deny", cfg!(feature = "run-cargo-deny"), &[]),
+    ("doc", cfg!(feature = "run-cargo-doc"), &[]),
+    ("hack", cfg!(feature = "run-cargo-hack"), &[]),
+    ("unused-deps", cfg!(feature = "run-unused-deps"), &[]),
+    ("coverage", cfg!(feature = "run-coverage"), &[]),
+    ("miri", cfg!(feature = "run-cargo-miri"), &["pre-push"]),
+    ("bench", cfg!(feature = "run-cargo-bench"), &["pre-push"]),
+    ("msrv-check", cfg!(feature = "run-msrv-check"), &[]),
+    ("target-check", cfg!(feature = "run-target-check"), &[]),
+    ("no-std-check", cfg!(feature = "run-no-std-check"), &[]),
+    ("typos", cfg!(feature = "run-typos"), &["pre-commit"]),
+    ("sort", cfg!(feature = "run-cargo-sort"), &["pre-commit"]),
+    ("taplo-fmt", cfg!(feature = "run-taplo-fmt"), &[]),
+    ("vet", cfg!(feature = "run-cargo-vet"), &[]),
+    ("license", cfg!(feature = "run-cargo-license"), &[]),
+    ("geiger", cfg!(feature = "run-cargo-geiger"), &[]),
+    (
+        "outdated",
+        cfg!(feature = "run-cargo-outdated"),
+        &["post-merge"],
+    ),
+    (
+        "semver-checks",
+        cfg!(feature = "run-cargo-semver-checks"),
+        &["pre-push"],
+    ),
+];
+
+pub fn command_names() -> Vec<&'static str> {
+    COMMANDS.iter().map(|&(name, _, _)| name).collect()
+}
+
+pub fn is_command(name: &str) -> bool {
+    COMMANDS.iter().any(|&(n, _, _)| n == name)
+}
+
+// Returns commands enabled by `run-*` features for the hook
+pub fn default_commands(hook: &str) -> Vec<&'static str> {
+    COMMANDS
+        .iter()
+        .filter(|&&(_, enabled, hooks)| enabled && (hooks.is_empty() || hooks.contains(&hook)))
+        .map(|&(name, _, _)| name)
+        .collect()
+}
+
+// Runs the command only when the tool is installed. Otherwise the command is skipped with message
+fn optional_cmd(tool: &str, installed: &str, c: &str) -> String {
+    described_optional_cmd(tool, installed, c, c)
+}
+
+// Same as `optional_cmd` but shows the description instead of the command itself
+fn described_optional_cmd(tool: &str, installed: &str, desc: &str, c: &str) -> String {
+    format!(
+        "\nif {} >/dev/null 2>&1; then\n    {}\n    {}\nelse\n    echo '{} is not installed. Skipped running `{}`'\nfi",
+        installed,
+        echo_cmd(desc),
+        c,
+        tool,
+        desc
+    )
+}
+
+// Runs criterion benchmarks and compares them with the saved baseline. The baseline is saved at the
+// first run. Benchmarks whose mean time regressed more than the threshold make the hook fail
+const BENCH_SCRIPT: &str = r#"
+criterion="${CARGO_TARGET_DIR:-target}/criterion"
+if [ -z "$(find "$criterion" -type d -name {baseline} 2>/dev/null)" ]; then
+    {echo_save}
+    {save}
+else
+    find "$criterion" -path '*/change/estimates.json' -exec rm -f {} +
+    {echo_compare}
+    {compare}
+    regressed="$(find "$criterion" -path '*/change/estimates.json' | while IFS= read -r f; do
+        change="$(sed -n 's/.*"mean":{"confidence_interval":{[^}]*},"point_estimate":\([^,]*\).*/\1/p' "$f")"
+        if [ -n "$change" ] && awk "BEGIN { exit !($change * 100 > {threshold}) }"; then
+            dirname "$(dirname "$f")"
+        fi
+    done)"
+    if [ -n "$regressed" ]; then
+        echo 'Benchmarks regressed more than {threshold}% compared to baseline '\''{baseline_name}'\'':'
+        echo "$regressed"
+        exit 1
+    fi
+fi"#;
+
+fn cmd_script(c: &str) -> String {
+    format!("\n{}\n{}", echo_cmd(c), c)
+}
+
+// Runs the command by the interpreter of other shell language. `${VAR}` and `${VAR:-default}` are
+// substituted before passing the command to the interpreter
+fn dialect_cmd(c: &str, interpreter: &str) -> String {
+    format!(
+        "\n{}\n{} \"{}\"",
+        echo_cmd(c),
+        interpreter,
+        escape_double_quote(c)
+    )
+}
+
+// Shows the command before running it. `${VAR}` and `${VAR:-default}` in the command are shown
+// after substitution
+fn echo_cmd(c: &str) -> String {
+    if c.contains("${") {
+        format!("echo \"+{}\"", escape_double_quote(c))
+    } else {
+        format!("echo '+{}'", escape_quote(c))
+    }
+}
+
+// Escapes the text to put it in double-quoted string. Only `${...}` is substituted in the string
+fn escape_double_quote(c: &str) -> String {
+    let mut s = String::new();
+    let mut chars = c.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '$' if chars.peek() == Some(&'{') => s.push('$'),
+            '$' | '"' | '`' | '\\' => {
+                s.push('\\');
+                s.push(ch);
+            }
+            _ => s.push(ch),
+        }
+    }
+    s
+}
+
+// Escapes single quotes to put the text in single-quoted string
+fn escape_quote(s: &str) -> String {
+    s.replace('\'', "'\\''")
+}
+
+// Quotes the text to put it in the script as one word. Text which does not need quoting is kept
+// as-is for readability
+pub fn quote(s: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "-_./:=+,@%^".contains(c);
+    if !s.is_empty() && s.chars().all(safe) {
+        s.to_string()
+    } else {
+        format!("'{}'", escape_quote(s))
+    }
+}
+
+// Quotes the path to put it in the script as one word. The script is UTF-8 text, so the bytes of a
+// path which is not valid UTF-8 are printed by `printf` with octal escapes instead
+fn quote_path(path: &Path) -> String {
+    match path.to_str() {
+        Some(s) => quote(s),
+        None => printf_path(path),
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn printf_path(path: &Path) -> String {
+    use std::os::unix::ffi::OsStrExt;
+
+    let format = path
+        .as_os_str()
+        .as_bytes()
+        .iter()
+        .map(|&b| match b {
+            b'%' => "%%".to_string(),
+            0x20..=0x7e if b != b'\\' && b != b'\'' => (b as char).to_string(),
+            _ => format!("\\{:03o}", b),
+        })
+        .collect::<String>();
+    format!("\"$(printf '{}')\"", format)
+}
+
+// Paths on Windows are UTF-16. Only unpaired surrogates are not valid UTF-8
+#[cfg(target_os = "windows")]
+fn printf_path(path: &Path) -> String {
+    quote(&path.to_string_lossy())
+}
+
+// Text put in a comment line. Line breaks would end the comment
+fn comment(s: &str) -> String {
+    s.replace(['\n', '\r'], " ")
+}
+
+// Checks licenses of dependencies listed by cargo-license with the allowlist file. Each line of the
+// file is a license ID. License expressions like `MIT OR Apache-2.0` are accepted when one of the
+// alternatives is allowed for every part joined with `AND`
+const LICENSE_SCRIPT: &str = r#"
+if ! {cargo} license --help >/dev/null 2>&1; then
+    echo 'cargo-license is not installed. Skipped checking licenses of dependencies'
+elif [ ! -f '{allowlist}' ]; then
+    echo 'License allowlist {allowlist} is not found'
+    exit 1
+else
+    echo '+{cargo} license --tsv'
+    denied="$({cargo} license --tsv | awk -F '\t' -v allowlist='{allowlist}' '
+        BEGIN {
+            while ((getline line < allowlist) > 0) {
+                sub(/#.*/, "", line)
+                gsub(/^[ \t]+|[ \t]+$/, "", line)
+                if (line != "") allowed[line] = 1
+            }
+        }
+        NR > 1 && $5 != "" {
+            expr = $5
+            gsub(/[()]/, "", expr)
+            n = split(expr, conjuncts, / AND /)
+            for (i = 1; i <= n; i++) {
+                m = split(conjuncts[i], alternatives, / OR |\//)
+                ok = 0
+                for (j = 1; j <= m; j++) if (alternatives[j] in allowed) ok = 1
+                if (!ok) { print $1 " " $2 ": " $5; next }
+            }
+        }')"
+    if [ -n "$denied" ]; then
+        echo 'Licenses of these crates are not allowed in {allowlist}:'
+        echo "$denied"
+        exit 1
+    fi
+fi"#;
+
+// Counts unsafe code used by the crate and its dependencies from JSON output of cargo-geiger and
+// checks the total with the budget
+const GEIGER_SCRIPT: &str = r#"
+if ! {cargo} geiger --version >/dev/null 2>&1; then
+    echo 'cargo-geiger is not installed. Skipped counting unsafe code'
+else
+    echo '+{cargo} geiger --output-format Json'
+    count="$({cargo} geiger --output-format Json | grep -oE '"used":\{("[a-z_]+":\{[^{}]*\},?)*\}' | grep -oE '"unsafe_":[0-9]+' | awk -F: '{ n += $2 } END { print n + 0 }')"
+    echo "Unsafe code count: $count (budget: {budget})"
+    if [ "$count" -gt {budget} ]; then
+        echo 'Unsafe code count exceeds the budget {budget}'
+        exit 1
+    fi
+fi"#;
+
+// Lists files which have unstaged changes before commands such as `cargo fmt` modify them
+const UNSTAGED_SCRIPT: &str = "\nunstaged=\"$(git diff --name-only)\"";
+
+// Adds staged files to the index again after they were modified by commands such as `cargo fmt`.
+// Partially staged files are skipped not to add their unstaged changes to the commit
+const RESTAGE_SCRIPT: &str = r#"
+git diff --cached --name-only --diff-filter=ACMR | while IFS= read -r f; do
+    if printf '%s\n' "$unstaged" | grep -qxF -- "$f"; then
+        echo "Skipped staging $f again since it has unstaged changes" >&2
+    else
+        git add -- "$f"
+    fi
+done"#;
+
+// Runs the command which modifies files and stages them again
+fn restage_script(c: &str) -> String {
+    format!("{}{}{}", UNSTAGED_SCRIPT, cmd_script(c), RESTAGE_SCRIPT)
+}
+
+// Appends `--all` to the command when `run-for-all` feature is enabled
+fn for_all(c: &str) -> String {
+    if cfg!(feature = "run-for-all") {
+        format!("{} --all", c)
+    } else {
+        c.to_string()
+    }
+}
+
+// Quotes the argument as one word. Arguments containing `${VAR}` are put in double quotes so that
+// the variables are still substituted
+fn quote_arg(arg: &str) -> String {
+    if arg.contains("${") {
+        format!("\"{}\"", escape_double_quote(arg))
+    } else {
+        quote(arg)
+    }
+}
+
+// Appends configured arguments to the command. Default arguments are used when not configured
+fn with_args(c: &str, args: &Option<Vec<String>>, default: &[&str]) -> String {
+    let args = match args {
+        Some(args) => args.iter().map(String::as_str).collect(),
+        None => default.to_vec(),
+    };
+    let mut c = c.to_string();
+    for arg in args {
+        c.push(' ');
+        c.push_str(&quote_arg(arg));
+    }
+    c
+}
+
+fn command_script(name: &str, hook: &str, config: &Config) -> String {
+    let c = config.command(name);
+    let cargo = match c.toolchain {
+        Some(ref t) => format!("cargo +{}", t),
+        // Miri and cargo-udeps only work with nightly toolchain
+        None if name == "miri" || (name == "unused-deps" && c.tool.as_deref() == Some("udeps")) => {
+            "cargo +nightly".to_string()
+        }
+        None => "cargo".to_string(),
+    };
+
+    match name {
+        "test" => {
+            // cargo-nextest cannot run doc tests
+            let sub = if cfg!(feature = "doc-tests-only") {
+                "test --doc"
+            } else if cfg!(feature = "use-cargo-nextest") {
+                "nextest run"
+            } else {
+                "test"
+            };
+            let cmd = for_all(&format!("{} {}", cargo, sub));
+            cmd_script(&with_args(&cmd, &c.args, &[]))
+        }
+        "check" => cmd_script(&for_all(&format!("{} check --all-targets", cargo))),
+        "clippy" => {
+            let check = cmd_script(&with_args(
+                &for_all(&format!("{} clippy", cargo)),
+                &c.args,
+                &["--", "-D", "warnings"],
+            ));
+            // Fixed files can be staged again only before committing
+            if c.fix && hook == "pre-commit" {
+                let fix = format!("{} clippy --fix --allow-staged --allow-dirty", cargo);
+                format!("{}{}", restage_script(&for_all(&fix)), check)
+            } else {
+                check
+            }
+        }
+        "fmt" if c.fix && hook == "pre-commit" => {
+            restage_script(&for_all(&format!("{} fmt", cargo)))
+        }
+        "fmt" => cmd_script(&format!("{} -- --check", for_all(&format!("{} fmt", cargo)))),
+        "audit" => optional_cmd(
+            "cargo-audit",
+            &format!("{} audit --version", cargo),
+            &format!("{} audit", cargo),
+        ),
+        "deny" => {
+            let mut cmd = format!("{} deny check", cargo);
+            for check in &c.checks {
+                cmd.push(' ');
+                cmd.push_str(check);
+            }
+            optional_cmd("cargo-deny", &format!("{} deny --version", cargo), &cmd)
+        }
+        "doc" => cmd_script(&for_all(&format!(
+            "RUSTDOCFLAGS=\"-D warnings\" {} doc --no-deps",
+            cargo
+        ))),
+        "hack" => optional_cmd(
+            "cargo-hack",
+            &format!("{} hack --version", cargo),
+            &with_args(
+                &for_all(&format!("{} hack check", cargo)),
+                &c.args,
+                &["--each-feature"],
+            ),
+        ),
+        "coverage" => {
+            let (tool, fail_under) = if c.tool.as_deref() == Some("tarpaulin") {
+                ("tarpaulin", "--fail-under")
+            } else {
+                ("llvm-cov", "--fail-under-lines")
+            };
+            let mut cmd = for_all(&format!("{} {}", cargo, tool));
+            if let Some(threshold) = c.threshold {
+                cmd = format!("{} {} {}", cmd, fail_under, threshold);
+            }
+            optional_cmd(
+                &format!("cargo-{}", tool),
+                &format!("{} {} --version", cargo, tool),
+                &cmd,
+            )
+        }
+        "miri" => optional_cmd(
+            "Miri",
+            &format!("{} miri --version", cargo),
+            &with_args(&for_all(&format!("{} miri test", cargo)), &c.args, &[]),
+        ),
+        "bench" => {
+            let baseline = c.baseline.as_deref().unwrap_or("cargo-husky");
+            let save = format!("{} bench -- --save-baseline {}", cargo, quote(baseline));
+            let compare = format!("{} bench -- --baseline {}", cargo, quote(baseline));
+            BENCH_SCRIPT
+                .replace("{echo_save}", &echo_cmd(&save))
+                .replace("{save}", &save)
+                .replace("{echo_compare}", &echo_cmd(&compare))
+                .replace("{compare}", &compare)
+                .replace("{baseline_name}", &escape_quote(baseline))
+                .replace("{baseline}", &quote(baseline))
+                .replace("{threshold}", &c.threshold.unwrap_or(5.0).to_string())
+        }
+        "msrv-check" => match config.rust_version {
+            Some(ref v) => optional_cmd(
+                &format!("Rust {} toolchain", v),
+                // `cargo +<toolchain>` may start installing the toolchain when it is missing
+                &format!("rustup toolchain list | grep -q '^{}-'", v),
+                &for_all(&format!("cargo +{} check", v)),
+            ),
+            None => {
+                "\necho 'rust-version is not set in Cargo.toml. Skipped checking MSRV'".to_string()
+            }
+        },
+        "target-check" => {
+            let default = vec!["wasm32-unknown-unknown".to_string()];
+            c.targets
+                .as_ref()
+                .unwrap_or(&default)
+                .iter()
+                .map(|t| {
+                    // Standard library for the target is necessary to check the crate
+                    optional_cmd(
+                        &format!("Target {}", t),
+                        &format!(
+                            "! command -v rustup >/dev/null || rustup target list --installed | grep -qx '{}'",
+                            escape_quote(t)
+                        ),
+                        &for_all(&format!("{} check --target {}", cargo, quote(t))),
+                    )
+                })
+                .collect()
+        }
+        "no-std-check" => {
+            let mut cmd = for_all(&format!("{} check --no-default-features", cargo));
+            if !c.features.is_empty() {
+                cmd = format!("{} --features {}", cmd, quote(&c.features.join(",")));
+            }
+            cmd_script(&cmd)
+        }
+        // xargs may run the command without arguments when nothing is staged. typos checks all files
+        // in that case so it must be avoided
+        "typos" => described_optional_cmd(
+            "typos",
+            "typos --version",
+            "typos <staged files>",
+            "git diff --cached --name-only --diff-filter=ACMR -z | xargs -0 sh -c '[ \"$#\" -eq 0 ] || typos -- \"$@\"' typos",
+        ),
+        "sort" => optional_cmd(
+            "cargo-sort",
+            &format!("{} sort --version", cargo),
+            // cargo-sort does not accept `--all`
+            &if cfg!(feature = "run-for-all") {
+                format!("{} sort --check --workspace", cargo)
+            } else {
+                format!("{} sort --check", cargo)
+            },
+        ),
+        "taplo-fmt" => optional_cmd("taplo", "taplo --version", "taplo fmt --check"),
+        "vet" => optional_cmd(
+            "cargo-vet",
+            &format!("{} vet --version", cargo),
+            &format!("{} vet", cargo),
+        ),
+        "license" => LICENSE_SCRIPT.replace("{cargo}", &cargo).replace(
+            "{allowlist}",
+            &escape_quote(c.allowlist.as_deref().unwrap_or("license-allowlist.txt")),
+        ),
+        "geiger" => match c.budget {
+            Some(budget) => GEIGER_SCRIPT
+                .replace("{cargo}", &cargo)
+                .replace("{budget}", &budget.to_string()),
+            None => optional_cmd(
+                "cargo-geiger",
+                &format!("{} geiger --version", cargo),
+                &format!("{} geiger", cargo),
+            ),
+        },
+        "outdated" => optional_cmd(
+            "cargo-outdated",
+            &format!("{} outdated --version", cargo),
+            &format!("{} outdated --exit-code 0", cargo),
+        ),
+        // Breaking changes only matter when a new version is released. Checking is skipped when no
+        // version in Cargo.toml was changed since upstream branch
+        "semver-checks" => {
+            let cmd = format!("{} semver-checks check-release", cargo);
+            format!(
+                "\nif ! {} semver-checks --version >/dev/null 2>&1; then\n    echo 'cargo-semver-checks is not installed. Skipped running `{}`'\nelif git rev-parse -q --verify '@{{upstream}}' >/dev/null && ! git diff '@{{upstream}}' HEAD -- '*Cargo.toml' | grep -q '^+version'; then\n    echo 'Version is not changed. Skipped running `{}`'\nelse\n    echo '+{}'\n    {}\nfi",
+                cargo, cmd, cmd, cmd, cmd
+            )
+        }
+        "unused-deps" if c.tool.as_deref() == Some("udeps") => optional_cmd(
+            "cargo-udeps",
+            &format!("{} udeps --version", cargo),
+            &for_all(&format!("{} udeps", cargo)),
+        ),
+        "unused-deps" => optional_cmd(
+            "cargo-machete",
+            &format!("{} machete --version", cargo),
+            &format!("{} machete", cargo),
+        ),
+        _ => match c.run {
+            Some(ref run) => match c.dialect.as_deref() {
+                Some("powershell") => dialect_cmd(run, "pwsh -NoProfile -NonInteractive -Command"),
+                Some("fish") => dialect_cmd(run, "fish --no-config -c"),
+                Some("nushell") => dialect_cmd(run, "nu --no-config-file -c"),
+                _ => cmd_script(run),
+            },
+            None => unreachable!("unknown command: {}", name),
+        },
+    }
+}
+
+// Functions to read `.cargo-husky.local.toml` at runtime. The file is not shared with other
+// developers so it can skip commands or run additional commands in the repository root:
+//
+// ```toml
+// skip = ["coverage"] # Skipped in all hooks
+//
+// [pre-push]
+// skip = ["test"]
+// run = ["cargo test --lib"]
+// ```
+//
+// Only arrays written in one line are supported since the file is parsed by awk
+const LOCAL_SCRIPT: &str = r#"
+# Prints strings in the array at the key of the table in .cargo-husky.local.toml
+husky_local() {
+    [ -f .cargo-husky.local.toml ] || return 0
+    awk -v t="$1" -v k="$2" '/^[ 	]*\[/ { s = $0; gsub(/^[ 	]*\[[ 	]*|[ 	]*\].*$/, "", s); next }
+        s == t && $0 ~ "^[ 	]*" k "[ 	]*=" { sub(/^[^=]*=[ 	]*\[/, "")
+            while (match($0, /"[^"]*"/)) { print substr($0, RSTART + 1, RLENGTH - 2); $0 = substr($0, RSTART + RLENGTH) } }' .cargo-husky.local.toml
+}
+husky_skip() { { husky_local '' skip; husky_local '{hook}' skip; } | grep -qxF "$1"; }
+"#;
+
+// Runs commands added by `run` in `.cargo-husky.local.toml`
+const LOCAL_RUN_SCRIPT: &str = r#"
+husky_local '{hook}' run | while IFS= read -r c; do
+    echo "+$c"
+    eval "$c" </dev/null
+done"#;
+
+// Lists files changed by the commit or the push which runs the hook. `None` means the changes are
+// unknown in the hook
+fn changed_files(hook: &str) -> Option<&'static str> {
+    match hook {
+        "pre-commit" | "pre-merge-commit" => Some("git diff --cached --name-only"),
+        "pre-push" => Some("git rev-parse -q --verify '@{upstream}' >/dev/null 2>&1 || return 0\n    git diff --name-only '@{upstream}' HEAD"),
+        "post-merge" => Some("git diff --name-only ORIG_HEAD HEAD"),
+        _ => None,
+    }
+}
+
+// Checks changed files with patterns in `paths`. `*` in the patterns also matches `/`
+const CHANGED_SCRIPT: &str = r#"
+# Succeeds when a changed file matches one of the patterns
+husky_changed() {
+    {changed} | {
+        while IFS= read -r f; do
+            for p in "$@"; do
+                case "$f" in $p) exit 0 ;; esac
+            done
+        done
+        exit 1
+    }
+}
+"#;
+
+// Checks `$HUSKY_PROFILE` selects one of the profiles. `{profiles}` is replaced with the patterns
+// of `case` statement
+const PROFILE_SCRIPT: &str = r#"
+case "${HUSKY_PROFILE:-}" in
+    {profiles}) ;;
+    *)
+        echo "Unknown profile '$HUSKY_PROFILE' in \$HUSKY_PROFILE. Available profiles are: {available}"
+        exit 1
+        ;;
+esac
+
+# Succeeds when the profile selected by $HUSKY_PROFILE is one of the arguments
+husky_profile() {
+    for p in "$@"; do
+        [ "${HUSKY_PROFILE:-}" = "$p" ] && return 0
+    done
+    return 1
+}
+"#;
+
+// Condition to run the command in the hook
+fn condition(name: &str, hook: &str, config: &Config) -> String {
+    let mut cond = format!("! husky_skip '{}'", escape_quote(name));
+    if let Some(profiles) = config.command_profiles(hook, name) {
+        cond.push_str(" && husky_profile");
+        for p in profiles {
+            cond.push_str(&format!(" '{}'", escape_quote(p)));
+        }
+    }
+    let paths = config.command(name).paths;
+    if !paths.is_empty() && changed_files(hook).is_some() {
+        cond.push_str(" && husky_changed");
+        for p in paths {
+            cond.push_str(&format!(" '{}'", escape_quote(&p)));
+        }
+    }
+    cond
+}
+
+// GUI Git clients may run hooks with minimal $PATH which does not contain cargo. `{cargo_dir}` is
+// replaced with the directory of cargo which installed the hooks
+const PATH_SCRIPT: &str = r#"
+if ! command -v cargo >/dev/null 2>&1; then
+    if [ -f "${CARGO_HOME:-$HOME/.cargo}/env" ]; then
+        . "${CARGO_HOME:-$HOME/.cargo}/env"
+    fi
+    if ! command -v cargo >/dev/null 2>&1 && [ -x "${CARGO_HOME:-$HOME/.cargo}/bin/cargo" ]; then
+        PATH="$PATH:${CARGO_HOME:-$HOME/.cargo}/bin"
+        export PATH
+    fi
+    if ! command -v cargo >/dev/null 2>&1 && [ -n {cargo_dir} ]; then
+        PATH="$PATH:"{cargo_dir}
+        export PATH
+    fi
+fi
+"#;
+
+// Detects that Cargo.lock in the repository root locks a version of cargo-husky other than the
+// one which generated the hook. Such a hook is outdated until the crate is built again. `{version}`
+// is replaced with the version of cargo-husky
+const STALE_SCRIPT: &str = r#"
+# Prints why the hook is outdated
+husky_outdated() {
+    [ -f Cargo.lock ] || return 0
+    locked="$(awk -F '"' '$0 == "name = \"cargo-husky\"" { getline; print $2; exit }' Cargo.lock 2>/dev/null || true)"
+    [ -z "$locked" ] || [ "$locked" = '{version}' ] || echo "This hook was generated by cargo-husky v{version} but Cargo.lock locks v$locked"
+}
+"#;
+
+// Checks the hook is outdated. `{outdated}` is replaced with `OUTDATED_SCRIPT` or `HEALING_SCRIPT`
+const STALE_CHECK_SCRIPT: &str = r#"
+cargo_husky_outdated="$(husky_outdated)"
+{outdated}"#;
+
+const OUTDATED_SCRIPT: &str = r#"[ -z "$cargo_husky_outdated" ] || echo "cargo-husky: $cargo_husky_outdated. Build the crate again to update the hook" >&2
+"#;
+
+// Reinstalls hooks when the hook is outdated or `cargo-husky.json` recording the installation is
+// missing, then runs the updated hook. Tests are built since cargo-husky is a dev-dependency.
+// `$CARGO_HUSKY_HEALED` prevents building again when the hook is still outdated. `{rerun}` is
+// replaced with the command to run the updated hook
+const HEALING_SCRIPT: &str = r#"if [ -z "$cargo_husky_outdated" ] && [ ! -f "$(git rev-parse --git-common-dir)/cargo-husky.json" ]; then
+    cargo_husky_outdated='cargo-husky.json recording the installed hooks is missing'
+fi
+if [ -n "$cargo_husky_outdated" ] && [ -z "${CARGO_HUSKY_HEALED:-}" ] && [ -z "${CARGO_HUSKY_DONT_INSTALL_HOOKS:-}" ]; then
+    echo "cargo-husky: $cargo_husky_outdated. Reinstalling hooks with \`cargo test --no-run\`" >&2
+    CARGO_HUSKY_HEALED=1
+    export CARGO_HUSKY_HEALED
+    if cargo test --no-run </dev/null >&2; then
+        {rerun}
+    else
+        echo 'cargo-husky: Could not reinstall hooks. Running the outdated hook' >&2
+    fi
+fi
+"#;
+
+// Directory of cargo running the build script. Under rustup, `$CARGO` is the cargo of a toolchain
+// which does not understand `cargo +<toolchain>` nor rust-toolchain.toml, so the directory of rustup
+// proxies is used instead. On Windows, the path cannot be put in $PATH of sh as-is since it contains
+// a drive letter. Hooks in the tracked directory are shared with other developers whose cargo is in
+// other directories
+fn cargo_dir() -> Option<PathBuf> {
+    if cfg!(target_os = "windows") || cfg!(feature = "tracked-hooks-dir") {
+        return None;
+    }
+    let dir = Path::new(&env::var_os("CARGO")?).parent()?.to_path_buf();
+    let home = env::var_os("HOME").map(PathBuf::from);
+    let rustup_home = env::var_os("RUSTUP_HOME")
+        .map(PathBuf::from)
+        .or_else(|| home.as_ref().map(|h| h.join(".rustup")));
+    if !rustup_home.is_some_and(|h| dir.starts_with(h.join("toolchains"))) {
+        return Some(dir);
+    }
+    let cargo_home = env::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| home.map(|h| h.join(".cargo")))?;
+    let proxies = cargo_home.join("bin");
+    Some(proxies).filter(|dir| dir.join("cargo").is_file())
+}
+
+// Runs the hook script which had been put before cargo-husky installed the hook
+const CHAIN_SCRIPT: &str = r#"
+local_hook="$(dirname "$0")/{hook}.local"
+if [ -x "$local_hook" ]; then
+    echo "+$local_hook"
+    "$local_hook" "$@"
+fi"#;
+
+// Comments which delimit the block of cargo-husky embedded in an existing hook script
+pub const BLOCK_BEGIN: &str = "# BEGIN cargo-husky";
+pub const BLOCK_END: &str = "# END cargo-husky";
+
+// Comment which identifies the features and the configuration the hook script was generated with.
+// The hook script is regenerated when the hash differs even if the version is the same
+pub const CONFIG_HASH: &str = "# Config hash: ";
+
+// Comment put after the config hash which identifies the exact content of the hook script. It is
+// the hash of the shebang and all lines after this line, so checking edits does not depend on which
+// part of the script the config hash covers in the version which generated it
+const SCRIPT_HASH: &str = "# Script hash: ";
+
+// Comments which delimit helpers run by every hook. They depend on the machine such as the directory
+// of cargo, so they are not part of the config hash and changing them does not regenerate hooks
+const RUNTIME_BEGIN: &str = "# >>> cargo-husky runtime";
+const RUNTIME_END: &str = "# <<< cargo-husky runtime";
+
+// Part of the hook body covered by the config hash
+fn hashed_body(body: &str) -> String {
+    match (body.find(RUNTIME_BEGIN), body.find(RUNTIME_END)) {
+        (Some(begin), Some(end)) if begin < end => {
+            format!("{}{}", &body[..begin], &body[end + RUNTIME_END.len()..])
+        }
+        _ => body.to_string(),
+    }
+}
+
+// 64-bit FNV-1a hash. It is stable across Rust versions unlike `DefaultHasher`
+fn fnv1a(s: &str) -> u64 {
+    s.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+// Checks the hook script generated by cargo-husky was edited after it was generated by comparing
+// its content with the script hash. Scripts generated before the script hash was added are compared
+// with the config hash instead. Scripts generated without the hashes are not regarded as edited
+pub fn is_modified(script: &str) -> bool {
+    if let Some(i) = script.find(SCRIPT_HASH) {
+        let end = script[i..].find('\n').map_or(script.len(), |j| i + j + 1);
+        let hash = script[i + SCRIPT_HASH.len()..end].trim_end();
+        let shebang = script.find('\n').map_or(script, |j| &script[..j + 1]);
+        let content = format!("{}{}", shebang, &script[end..]);
+        return format!("{:016x}", fnv1a(&content)) != hash;
+    }
+    let shell = match script.lines().next().and_then(|l| l.strip_prefix("#!")) {
+        Some(shell) => shell,
+        None => return false,
+    };
+    let hash = match script.find(CONFIG_HASH) {
+        Some(i) => &script[i + CONFIG_HASH.len()..],
+        None => return false,
+    };
+    let (hash, body) = match hash.find('\n') {
+        Some(i) => (&hash[..i], &hash[i + 1..]),
+        None => return false,
+    };
+    let body = body.strip_prefix("#\n\n").unwrap_or(body);
+    let body = body.strip_suffix('\n').unwrap_or(body);
+    format!(
+        "{:016x}",
+        fnv1a(&format!("{}\n{}", shell, hashed_body(body)))
+    ) != hash
+}
+
+// Comments which show the version of cargo-husky. `hook_already_exists` checks the 3rd line
+fn header(shell: &str, body: &str) -> String {
+    // Hooks in the tracked directory are committed. Paths on the machine should not be in them
+    let paths = if cfg!(feature = "tracked-hooks-dir") {
+        String::new()
+    } else {
+        let out_dir = env::var_os("OUT_DIR").unwrap_or_default();
+        format!(
+            "# Generated by script {}{}build{}main.rs\n# Output at {}\n",
+            comment(env!("CARGO_MANIFEST_DIR")),
+            path::MAIN_SEPARATOR,
+            path::MAIN_SEPARATOR,
+            comment(&path::Path::new(&out_dir).display().to_string()),
+        )
+    };
+    format!(
+        "#\n# This hook was set by cargo-husky v{}: {}\n{}{}{:016x}\n#\n",
+        env!("CARGO_PKG_VERSION"),
+        env!("CARGO_PKG_HOMEPAGE"),
+        paths,
+        CONFIG_HASH,
+        fnv1a(&format!("{}\n{}", shell, hashed_body(body))),
+    )
+}
+
+pub fn write_script<W: io::Write>(w: &mut W, hook: &str, config: &Config) -> Result<()> {
+    let shell = config.shell(hook);
+    let body = script_body(hook, config, "exec \"$0\" \"$@\"");
+    let shebang = format!("#!{}\n", shell);
+    let (header, body) = (header(shell, &body), format!("\n{}\n", body));
+    // The script hash is put on the line after the config hash
+    let i = header.find(CONFIG_HASH).unwrap_or(0);
+    let i = header[i..].find('\n').map_or(header.len(), |j| i + j + 1);
+    let rest = format!("{}{}", &header[i..], body);
+    write!(
+        w,
+        "{}{}{}{:016x}\n{}",
+        shebang,
+        &header[..i],
+        SCRIPT_HASH,
+        fnv1a(&format!("{}{}", shebang, rest)),
+        rest
+    )?;
+    Ok(())
+}
+
+// Writes the block embedded in an existing hook script. Commands are run in a subshell so that
+// `set -e` and functions do not affect the rest of the script
+pub fn write_block<W: io::Write>(w: &mut W, hook: &str, config: &Config) -> Result<()> {
+    // Running the whole hook again would run the rest of the existing script twice
+    let body = script_body(
+        hook,
+        config,
+        "echo 'cargo-husky: Reinstalled hooks are run from next time' >&2",
+    );
+    writeln!(
+        w,
+        "{}\n{}(\n{}\n) || exit $?\n{}",
+        BLOCK_BEGIN,
+        header("", &body),
+        body,
+        BLOCK_END
+    )?;
+    Ok(())
+}
+
+// `rerun` is the command to run the hook again after self-healing hooks reinstalled it
+fn script_body(hook: &str, config: &Config, rerun: &str) -> String {
+    let commands = config.commands(hook);
+    let mut script = if cfg!(feature = "chain-existing-hooks") {
+        CHAIN_SCRIPT.replace("{hook}", hook)
+    } else {
+        String::new()
+    };
+    // Body of each command is not indented so that the commands look the same as without guards
+    script += &commands
+        .iter()
+        .map(|name| {
+            format!(
+                "\nif {}; then{}\nfi",
+                condition(name, hook, config),
+                command_script(name, hook, config)
+            )
+        })
+        .collect::<String>();
+    script.push_str(&LOCAL_RUN_SCRIPT.replace("{hook}", hook));
+
+    let runtime = format!(
+        "{}{}{}",
+        PATH_SCRIPT.replace(
+            "{cargo_dir}",
+            &cargo_dir().map_or_else(|| quote(""), |d| quote_path(&d)),
+        ),
+        STALE_SCRIPT.replace("{version}", env!("CARGO_PKG_VERSION")),
+        LOCAL_SCRIPT.replace("{hook}", hook),
+    );
+    let outdated = if cfg!(feature = "self-healing-hooks") {
+        HEALING_SCRIPT.replace("{rerun}", rerun)
+    } else {
+        OUTDATED_SCRIPT.to_string()
+    };
+    let mut functions = format!(
+        "{}{}{}\n{}",
+        RUNTIME_BEGIN,
+        runtime,
+        RUNTIME_END,
+        STALE_CHECK_SCRIPT.replace("{outdated}", &outdated)
+    );
+    let profiles = config.profiles();
+    if !profiles.is_empty() {
+        let patterns = profiles
+            .iter()
+            .map(|p| format!("|'{}'", p))
+            .collect::<String>();
+        functions.push_str(
+            &PROFILE_SCRIPT
+                .replace("{profiles}", &format!("''{}", patterns))
+                .replace("{available}", &profiles.join(", ")),
+        );
+    }
+    if let Some(changed) = changed_files(hook) {
+        if commands
+            .iter()
+            .any(|name| !config.command(name).paths.is_empty())
+        {
+            functions.push_str(&CHANGED_SCRIPT.replace("{changed}", changed));
+        }
+    }
+
+    let env = config
+        .env(hook)
+        .iter()
+        .map(|(name, value)| format!("export {}=\"{}\"\n", name, escape_double_quote(value)))
+        .collect::<String>();
+
+    // `{{hook_name}}` in header and footer is replaced with the hook name
+    let fragment = |s: &Option<String>| {
+        s.as_ref()
+            .map(|s| s.trim_end().replace("{{hook_name}}", hook))
+    };
+    let header = fragment(&config.header)
+        .map(|s| s + "\n")
+        .unwrap_or_default();
+    let footer = fragment(&config.footer)
+        .map(|s| "\n".to_string() + &s)
+        .unwrap_or_default();
+    // Hooks are always written with LF line endings since sh cannot run scripts with CRLF. CRLF
+    // comes from multi-line strings in configuration files or sources checked out with
+    // `core.autocrlf`
+    format!("{}set -e\n{}{}{}{}", header, env, functions, script, footer).replace("\r\n", "\n")
+}
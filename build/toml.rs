@@ -0,0 +1,496 @@
+// Small TOML parser to read cargo-husky configuration without depending on other crates.
+// It supports everything which appears in Cargo.toml in practice. Date-time values are not
+// validated and are read as strings.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+pub type Table = BTreeMap<String, Value>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    Array(Vec<Value>),
+    Table(Table),
+}
+
+impl Value {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(..) => "string",
+            Value::Integer(..) => "integer",
+            Value::Float(..) => "float",
+            Value::Boolean(..) => "boolean",
+            Value::Array(..) => "array",
+            Value::Table(..) => "table",
+        }
+    }
+}
+
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+type Result<T> = ::std::result::Result<T, ParseError>;
+
+// Returns the table at the dotted path in the given table. Tables which don't exist yet are
+// created. When an array of tables is found on the way, its last element is used.
+fn table_at<'a>(
+    mut table: &'a mut Table,
+    path: &[String],
+) -> ::std::result::Result<&'a mut Table, String> {
+    for key in path {
+        let value = table
+            .entry(key.clone())
+            .or_insert_with(|| Value::Table(Table::new()));
+        table = match value {
+            Value::Table(t) => t,
+            Value::Array(a) => match a.last_mut() {
+                Some(Value::Table(t)) => t,
+                _ => return Err(format!("key '{}' is not a table", key)),
+            },
+            _ => return Err(format!("key '{}' is not a table", key)),
+        };
+    }
+    Ok(table)
+}
+
+fn insert(table: &mut Table, path: &[String], value: Value) -> ::std::result::Result<(), String> {
+    let (last, parents) = path.split_last().unwrap();
+    let table = table_at(table, parents)?;
+    if table.contains_key(last) {
+        return Err(format!("key '{}' is defined twice", last));
+    }
+    table.insert(last.clone(), value);
+    Ok(())
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+    line: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error<T, S: Into<String>>(&self, message: S) -> Result<T> {
+        Err(ParseError {
+            line: self.line,
+            message: message.into(),
+        })
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn eat(&mut self, s: &str) -> bool {
+        if self.rest().starts_with(s) {
+            for _ in s.chars() {
+                self.bump();
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        match self.peek() {
+            Some(p) if p == c => {
+                self.bump();
+                Ok(())
+            }
+            Some(p) => self.error(format!("expected '{}' but found '{}'", c, p)),
+            None => self.error(format!("expected '{}' but reached end of file", c)),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(' ') | Some('\t') = self.peek() {
+            self.bump();
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some('#') {
+            while let Some(c) = self.peek() {
+                if c == '\n' {
+                    break;
+                }
+                self.bump();
+            }
+        }
+    }
+
+    // Skips whitespaces, newlines and comments. Used inside arrays and between statements
+    fn skip_blank(&mut self) {
+        loop {
+            self.skip_whitespace();
+            self.skip_comment();
+            if !self.eat("\n") && !self.eat("\r\n") {
+                return;
+            }
+        }
+    }
+
+    fn end_of_line(&mut self) -> Result<()> {
+        self.skip_whitespace();
+        self.skip_comment();
+        if self.peek().is_none() || self.eat("\n") || self.eat("\r\n") {
+            Ok(())
+        } else {
+            self.error("expected newline after value")
+        }
+    }
+
+    fn parse(&mut self) -> Result<Table> {
+        let mut root = Table::new();
+        let mut current = vec![];
+        loop {
+            self.skip_blank();
+            match self.peek() {
+                None => return Ok(root),
+                Some('[') => {
+                    self.bump();
+                    let is_array = self.eat("[");
+                    self.skip_whitespace();
+                    let path = self.key()?;
+                    self.skip_whitespace();
+                    self.expect(']')?;
+                    if is_array {
+                        self.expect(']')?;
+                        let (last, parents) = path.split_last().unwrap();
+                        let result = table_at(&mut root, parents).and_then(|parent| {
+                            let value = parent
+                                .entry(last.clone())
+                                .or_insert_with(|| Value::Array(vec![]));
+                            match value {
+                                Value::Array(a) => {
+                                    a.push(Value::Table(Table::new()));
+                                    Ok(())
+                                }
+                                _ => Err(format!("key '{}' is not an array of tables", last)),
+                            }
+                        });
+                        if let Err(msg) = result {
+                            return self.error(msg);
+                        }
+                    } else if let Err(msg) = table_at(&mut root, &path) {
+                        return self.error(msg);
+                    }
+                    current = path;
+                    self.end_of_line()?;
+                }
+                Some(_) => {
+                    let (path, value) = self.key_value()?;
+                    let result =
+                        table_at(&mut root, &current).and_then(|t| insert(t, &path, value));
+                    if let Err(msg) = result {
+                        return self.error(msg);
+                    }
+                    self.end_of_line()?;
+                }
+            }
+        }
+    }
+
+    fn key_value(&mut self) -> Result<(Vec<String>, Value)> {
+        let path = self.key()?;
+        self.skip_whitespace();
+        self.expect('=')?;
+        self.skip_whitespace();
+        let value = self.value()?;
+        Ok((path, value))
+    }
+
+    // Parses dotted key such as `a."b".c`
+    fn key(&mut self) -> Result<Vec<String>> {
+        let mut path = vec![];
+        loop {
+            let key = match self.peek() {
+                Some('"') => self.basic_string()?,
+                Some('\'') => self.literal_string()?,
+                _ => {
+                    let start = self.pos;
+                    while let Some(c) = self.peek() {
+                        if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                            self.bump();
+                        } else {
+                            break;
+                        }
+                    }
+                    if start == self.pos {
+                        return match self.peek() {
+                            Some(c) => self.error(format!("invalid character '{}' in key", c)),
+                            None => self.error("expected key but reached end of file"),
+                        };
+                    }
+                    self.src[start..self.pos].to_string()
+                }
+            };
+            path.push(key);
+            self.skip_whitespace();
+            if !self.eat(".") {
+                return Ok(path);
+            }
+            self.skip_whitespace();
+        }
+    }
+
+    fn value(&mut self) -> Result<Value> {
+        let rest = self.rest();
+        if rest.starts_with("\"\"\"") {
+            self.multi_line_string('"').map(Value::String)
+        } else if rest.starts_with("'''") {
+            self.multi_line_string('\'').map(Value::String)
+        } else if rest.starts_with('"') {
+            self.basic_string().map(Value::String)
+        } else if rest.starts_with('\'') {
+            self.literal_string().map(Value::String)
+        } else if rest.starts_with('[') {
+            self.array()
+        } else if rest.starts_with('{') {
+            self.inline_table()
+        } else if self.eat("true") {
+            Ok(Value::Boolean(true))
+        } else if self.eat("false") {
+            Ok(Value::Boolean(false))
+        } else if is_date_time(rest) {
+            Ok(self.date_time())
+        } else {
+            self.number()
+        }
+    }
+
+    fn escape(&mut self) -> Result<char> {
+        let c = match self.bump() {
+            Some('b') => '\u{8}',
+            Some('t') => '\t',
+            Some('n') => '\n',
+            Some('f') => '\u{c}',
+            Some('r') => '\r',
+            Some('"') => '"',
+            Some('\\') => '\\',
+            Some(u @ 'u') | Some(u @ 'U') => {
+                let len = if u == 'u' { 4 } else { 8 };
+                let rest = self.rest();
+                let code = rest
+                    .get(..len)
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .and_then(::std::char::from_u32);
+                match code {
+                    Some(c) => {
+                        self.pos += len;
+                        c
+                    }
+                    None => return self.error("invalid unicode escape in string"),
+                }
+            }
+            Some(c) => return self.error(format!("invalid escape sequence '\\{}' in string", c)),
+            None => return self.error("unterminated string"),
+        };
+        Ok(c)
+    }
+
+    fn basic_string(&mut self) -> Result<String> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            match self.bump() {
+                Some('"') => return Ok(s),
+                Some('\\') => s.push(self.escape()?),
+                Some('\n') | None => return self.error("unterminated string"),
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> Result<String> {
+        self.expect('\'')?;
+        let mut s = String::new();
+        loop {
+            match self.bump() {
+                Some('\'') => return Ok(s),
+                Some('\n') | None => return self.error("unterminated string"),
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    fn multi_line_string(&mut self, quote: char) -> Result<String> {
+        let delim = if quote == '"' { "\"\"\"" } else { "'''" };
+        self.eat(delim);
+        // A newline immediately following the opening delimiter is trimmed
+        if !self.eat("\n") {
+            self.eat("\r\n");
+        }
+        let mut s = String::new();
+        loop {
+            if self.eat(delim) {
+                // Up to two quotes are allowed just before the closing delimiter
+                for _ in 0..2 {
+                    if self.peek() == Some(quote) {
+                        self.bump();
+                        s.push(quote);
+                    }
+                }
+                return Ok(s);
+            }
+            match self.bump() {
+                Some('\\') if quote == '"' => {
+                    let rest = self.rest().trim_start_matches([' ', '\t']);
+                    if rest.starts_with('\n') || rest.starts_with("\r\n") {
+                        // Line ending backslash trims all whitespaces and newlines after it
+                        while let Some(' ') | Some('\t') | Some('\n') | Some('\r') = self.peek() {
+                            self.bump();
+                        }
+                    } else {
+                        s.push(self.escape()?);
+                    }
+                }
+                Some(c) => s.push(c),
+                None => return self.error("unterminated multi-line string"),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value> {
+        self.expect('[')?;
+        let mut values = vec![];
+        loop {
+            self.skip_blank();
+            if self.eat("]") {
+                return Ok(Value::Array(values));
+            }
+            values.push(self.value()?);
+            self.skip_blank();
+            if self.eat("]") {
+                return Ok(Value::Array(values));
+            }
+            self.expect(',')?;
+        }
+    }
+
+    fn inline_table(&mut self) -> Result<Value> {
+        self.expect('{')?;
+        let mut table = Table::new();
+        self.skip_whitespace();
+        if self.eat("}") {
+            return Ok(Value::Table(table));
+        }
+        loop {
+            self.skip_whitespace();
+            let (path, value) = self.key_value()?;
+            if let Err(msg) = insert(&mut table, &path, value) {
+                return self.error(msg);
+            }
+            self.skip_whitespace();
+            if self.eat("}") {
+                return Ok(Value::Table(table));
+            }
+            self.expect(',')?;
+        }
+    }
+
+    fn date_time(&mut self) -> Value {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            let time_follows = c == ' ' && is_date_time(&self.rest()[1..]);
+            if c.is_ascii_alphanumeric() || "+-:.".contains(c) || time_follows {
+                self.bump();
+            } else {
+                break;
+            }
+        }
+        Value::String(self.src[start..self.pos].to_string())
+    }
+
+    fn number(&mut self) -> Result<Value> {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.' || c == '_' {
+                self.bump();
+            } else {
+                break;
+            }
+        }
+        let token = &self.src[start..self.pos];
+        if token.is_empty() {
+            return match self.peek() {
+                Some(c) => self.error(format!("unexpected character '{}' for value", c)),
+                None => self.error("expected value but reached end of file"),
+            };
+        }
+
+        let digits = token.replace('_', "");
+        let (sign, unsigned) = match digits.chars().next() {
+            Some('-') => (-1, &digits[1..]),
+            Some('+') => (1, &digits[1..]),
+            _ => (1, &digits[..]),
+        };
+        let radix = match unsigned.get(..2) {
+            Some("0x") => 16,
+            Some("0o") => 8,
+            Some("0b") => 2,
+            _ => 10,
+        };
+        if radix != 10 {
+            if let Ok(i) = i64::from_str_radix(&unsigned[2..], radix) {
+                return Ok(Value::Integer(i));
+            }
+        } else if let Ok(i) = unsigned.parse::<i64>() {
+            return Ok(Value::Integer(sign * i));
+        }
+        match unsigned {
+            "inf" => return Ok(Value::Float(sign as f64 * f64::INFINITY)),
+            "nan" => return Ok(Value::Float(f64::NAN)),
+            _ => {}
+        }
+        if unsigned.starts_with(|c: char| c.is_ascii_digit()) {
+            if let Ok(f) = unsigned.parse::<f64>() {
+                return Ok(Value::Float(sign as f64 * f));
+            }
+        }
+        self.error(format!("invalid value '{}'", token))
+    }
+}
+
+// Offset date-time, local date-time or local date (1979-05-27...) and local time (07:32:00)
+fn is_date_time(s: &str) -> bool {
+    let b = s.as_bytes();
+    let digits =
+        |r: ::std::ops::Range<usize>| b.len() >= r.end && b[r].iter().all(u8::is_ascii_digit);
+    digits(0..4) && b.get(4) == Some(&b'-') || digits(0..2) && b.get(2) == Some(&b':')
+}
+
+pub fn parse(src: &str) -> Result<Table> {
+    Parser {
+        src,
+        pos: 0,
+        line: 1,
+    }
+    .parse()
+}
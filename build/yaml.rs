@@ -0,0 +1,417 @@
+// Small YAML parser to read `.pre-commit-config.yaml` without depending on other crates. It
+// supports block mappings and sequences, flow sequences and mappings, quoted and plain scalars and
+// literal and folded block scalars. Anchors, aliases, tags and multi-line plain scalars are not
+// supported. Scalars are parsed as strings except `true` and `false`, and null is an empty string.
+
+use toml::{ParseError, Table, Value};
+
+type Result<T> = ::std::result::Result<T, ParseError>;
+
+struct Parser<'a> {
+    lines: Vec<&'a str>,
+    pos: usize,
+    // Column where the rest of the current line starts when its beginning was already consumed
+    // such as `- ` of sequence item
+    column: Option<usize>,
+}
+
+// Returns the line without comment. `#` starts a comment at the beginning or after a space
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut prev = ' ';
+    for (i, c) in line.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '#' && (prev == ' ' || prev == '\t') => return &line[..i],
+            None if (c == '"' || c == '\'') && (prev == ' ' || "[{,:-".contains(prev)) => {
+                quote = Some(c)
+            }
+            None => {}
+        }
+        prev = c;
+    }
+    line
+}
+
+fn is_item(text: &str) -> bool {
+    text == "-" || text.starts_with("- ")
+}
+
+// Returns the position of `:` which separates the key and the value of the mapping entry
+fn find_colon(text: &str) -> Option<usize> {
+    let mut quote = None;
+    let bytes = text.as_bytes();
+    for (i, c) in text.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if (c == '"' || c == '\'') && i == 0 => quote = Some(c),
+            None if c == ':' && matches!(bytes.get(i + 1), None | Some(b' ') | Some(b'\t')) => {
+                return Some(i)
+            }
+            None => {}
+        }
+    }
+    None
+}
+
+fn plain(text: &str) -> Value {
+    match text {
+        "true" | "True" | "TRUE" => Value::Boolean(true),
+        "false" | "False" | "FALSE" => Value::Boolean(false),
+        "~" | "null" | "Null" | "NULL" => Value::String(String::new()),
+        _ => Value::String(text.to_string()),
+    }
+}
+
+// Parses the quoted scalar at the beginning of the text. The rest of the text is returned with it
+fn quoted(text: &str) -> ::std::result::Result<(String, &str), String> {
+    let mut chars = text.char_indices();
+    let quote = match chars.next() {
+        Some((_, q)) => q,
+        None => return Err("expected quoted string".to_string()),
+    };
+    let mut value = String::new();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\'' if quote == '\'' => {
+                if text[i + 1..].starts_with('\'') {
+                    chars.next();
+                    value.push('\'');
+                } else {
+                    return Ok((value, &text[i + 1..]));
+                }
+            }
+            '"' if quote == '"' => return Ok((value, &text[i + 1..])),
+            '\\' if quote == '"' => match chars.next() {
+                Some((_, 'n')) => value.push('\n'),
+                Some((_, 't')) => value.push('\t'),
+                Some((_, 'r')) => value.push('\r'),
+                Some((_, '0')) => value.push('\0'),
+                Some((_, c @ '"')) | Some((_, c @ '\\')) | Some((_, c @ '/')) => value.push(c),
+                Some((_, c)) => return Err(format!("unsupported escape sequence '\\{}'", c)),
+                None => break,
+            },
+            c => value.push(c),
+        }
+    }
+    Err(format!("unclosed {} string", quote))
+}
+
+// Parser of flow collections such as `[a, "b"]` and `{a: b}`
+struct Flow<'a> {
+    src: &'a str,
+}
+
+impl<'a> Flow<'a> {
+    fn skip_spaces(&mut self) {
+        self.src = self.src.trim_start();
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_spaces();
+        if self.src.starts_with(c) {
+            self.src = &self.src[c.len_utf8()..];
+            true
+        } else {
+            false
+        }
+    }
+
+    fn value(&mut self) -> ::std::result::Result<Value, String> {
+        self.skip_spaces();
+        if self.eat('[') {
+            let mut items = vec![];
+            while !self.eat(']') {
+                items.push(self.value()?);
+                if !self.eat(',') && !self.src.starts_with(']') {
+                    return Err("expected ',' or ']' in flow sequence".to_string());
+                }
+            }
+            Ok(Value::Array(items))
+        } else if self.eat('{') {
+            let mut table = Table::new();
+            while !self.eat('}') {
+                let key = match self.value()? {
+                    Value::String(s) => s,
+                    v => return Err(format!("expected string key but got {}", v.type_name())),
+                };
+                let value = if self.eat(':') {
+                    self.value()?
+                } else {
+                    Value::String(String::new())
+                };
+                if table.insert(key.clone(), value).is_some() {
+                    return Err(format!("duplicate key '{}'", key));
+                }
+                if !self.eat(',') && !self.src.starts_with('}') {
+                    return Err("expected ',' or '}' in flow mapping".to_string());
+                }
+            }
+            Ok(Value::Table(table))
+        } else if self.src.starts_with('"') || self.src.starts_with('\'') {
+            let (value, rest) = quoted(self.src)?;
+            self.src = rest;
+            Ok(Value::String(value))
+        } else {
+            let end = self
+                .src
+                .char_indices()
+                .find(|&(i, c)| {
+                    ",[]{}".contains(c) || c == ':' && self.src[i + 1..].starts_with(' ')
+                })
+                .map(|(i, _)| i)
+                .unwrap_or(self.src.len());
+            let text = self.src[..end].trim_end();
+            if text.is_empty() {
+                return Err("expected value in flow collection".to_string());
+            }
+            self.src = &self.src[end..];
+            Ok(plain(text))
+        }
+    }
+}
+
+impl<'a> Parser<'a> {
+    fn error<T, S: Into<String>>(&self, message: S) -> Result<T> {
+        Err(ParseError {
+            line: self.pos + 1,
+            message: message.into(),
+        })
+    }
+
+    // Returns the column and the content of the current line. Empty lines are skipped
+    fn peek(&mut self) -> Result<Option<(usize, &'a str)>> {
+        if self.column.is_none() {
+            while self
+                .lines
+                .get(self.pos)
+                .is_some_and(|l| strip_comment(l).trim().is_empty())
+            {
+                self.pos += 1;
+            }
+        }
+        let line = match self.lines.get(self.pos) {
+            Some(line) => *line,
+            None => return Ok(None),
+        };
+        let column = self
+            .column
+            .unwrap_or_else(|| line.len() - line.trim_start_matches(' ').len());
+        let text = strip_comment(&line[column..]).trim_end();
+        if text.starts_with('\t') {
+            return self.error("tabs are not allowed for indentation");
+        }
+        Ok(Some((column, text)))
+    }
+
+    fn advance(&mut self) {
+        self.pos += 1;
+        self.column = None;
+    }
+
+    // Parses the block collection whose entries start at the column
+    fn block(&mut self, indent: usize) -> Result<Value> {
+        match self.peek()? {
+            Some((_, text)) if is_item(text) => self.sequence(indent),
+            _ => self.mapping(indent),
+        }
+    }
+
+    // Parses the node following `key:` or `-` which has nothing after it on the same line
+    fn nested(&mut self, indent: usize, in_mapping: bool) -> Result<Value> {
+        self.advance();
+        match self.peek()? {
+            Some((column, _)) if column > indent => self.block(column),
+            // Sequence in mapping may be indented at the same column as the key
+            Some((column, text)) if column == indent && in_mapping && is_item(text) => {
+                self.sequence(column)
+            }
+            _ => Ok(Value::String(String::new())),
+        }
+    }
+
+    fn sequence(&mut self, indent: usize) -> Result<Value> {
+        let mut items = vec![];
+        while let Some((column, text)) = self.peek()? {
+            if column < indent || column == indent && !is_item(text) {
+                break;
+            }
+            if column > indent {
+                return self.error("unexpected indentation");
+            }
+            let rest = text[1..].trim_start();
+            let item = if rest.is_empty() {
+                self.nested(indent, false)?
+            } else {
+                let column = column + text.len() - rest.len();
+                self.column = Some(column);
+                if is_item(rest) || find_colon(rest).is_some() {
+                    self.block(column)?
+                } else {
+                    self.scalar(column)?
+                }
+            };
+            items.push(item);
+        }
+        Ok(Value::Array(items))
+    }
+
+    fn mapping(&mut self, indent: usize) -> Result<Value> {
+        let mut table = Table::new();
+        while let Some((column, text)) = self.peek()? {
+            if column < indent {
+                break;
+            }
+            if column > indent {
+                return self.error("unexpected indentation");
+            }
+            let colon = match find_colon(text) {
+                Some(colon) => colon,
+                None => return self.error(format!("expected mapping key but got '{}'", text)),
+            };
+            let key = text[..colon].trim_end();
+            let key = if key.starts_with('"') || key.starts_with('\'') {
+                match quoted(key) {
+                    Ok((key, "")) => key,
+                    Ok(..) => return self.error(format!("invalid key {}", key)),
+                    Err(message) => return self.error(message),
+                }
+            } else {
+                key.to_string()
+            };
+            if table.contains_key(&key) {
+                return self.error(format!("duplicate key '{}'", key));
+            }
+            let rest = text[colon + 1..].trim_start();
+            let value = if rest.is_empty() {
+                self.nested(indent, true)?
+            } else if rest.starts_with('|') || rest.starts_with('>') {
+                self.block_scalar(indent, rest)?
+            } else {
+                let column = column + text.len() - rest.len();
+                self.column = Some(column);
+                self.scalar(column)?
+            };
+            table.insert(key, value);
+        }
+        Ok(Value::Table(table))
+    }
+
+    // Parses the scalar or the flow collection at the column of the current line
+    fn scalar(&mut self, column: usize) -> Result<Value> {
+        let line = strip_comment(&self.lines[self.pos][column..]).trim_end();
+        let value = if line.starts_with('[') || line.starts_with('{') {
+            // Flow collections may continue to the following lines until they are closed
+            let mut src = line.to_string();
+            loop {
+                let mut flow = Flow { src: &src };
+                match flow.value() {
+                    Ok(value) if flow.src.trim().is_empty() => break value,
+                    Ok(..) => return self.error("unexpected characters after flow collection"),
+                    Err(..) if self.pos + 1 < self.lines.len() => {
+                        self.advance();
+                        src.push(' ');
+                        src.push_str(strip_comment(self.lines[self.pos]).trim());
+                    }
+                    Err(message) => return self.error(message),
+                }
+            }
+        } else if line.starts_with('"') || line.starts_with('\'') {
+            match quoted(line) {
+                Ok((value, rest)) if rest.trim().is_empty() => Value::String(value),
+                Ok(..) => return self.error("unexpected characters after quoted string"),
+                Err(message) => return self.error(message),
+            }
+        } else if line.starts_with('&') || line.starts_with('*') || line.starts_with('!') {
+            return self.error("anchors, aliases and tags are not supported");
+        } else {
+            plain(line)
+        };
+        self.advance();
+        // Plain scalars continued to the following lines are not supported
+        if let Some((next, text)) = self.peek()? {
+            if next > column && !is_item(text) && find_colon(text).is_none() {
+                return self.error("multi-line plain scalars are not supported");
+            }
+        }
+        Ok(value)
+    }
+
+    // Parses the literal (`|`) or folded (`>`) block scalar whose lines are indented more than the
+    // mapping key
+    fn block_scalar(&mut self, indent: usize, header: &str) -> Result<Value> {
+        let folded = header.starts_with('>');
+        let chomping = &header[1..];
+        if !["", "-", "+"].contains(&chomping) {
+            return self.error(format!("unsupported block scalar header '{}'", header));
+        }
+        self.advance();
+        let mut lines = vec![];
+        let mut content_indent = None;
+        while let Some(&line) = self.lines.get(self.pos) {
+            if line.trim().is_empty() {
+                lines.push("");
+                self.advance();
+                continue;
+            }
+            let column = line.len() - line.trim_start_matches(' ').len();
+            let min = *content_indent.get_or_insert(column);
+            if column <= indent || column < min {
+                break;
+            }
+            lines.push(&line[min..]);
+            self.advance();
+        }
+        // Trailing empty lines are consumed but belong to the scalar only with `+`
+        let content = lines
+            .iter()
+            .rposition(|l| !l.is_empty())
+            .map_or(0, |i| i + 1);
+        let trailing = lines.len() - content;
+        let mut value = String::new();
+        for (i, line) in lines[..content].iter().enumerate() {
+            // Folded lines are joined with a space and each empty line is a line break
+            if !folded && i > 0 || folded && line.is_empty() {
+                value.push('\n');
+            } else if folded && i > 0 && !lines[i - 1].is_empty() {
+                value.push(' ');
+            }
+            value.push_str(line);
+        }
+        match chomping {
+            "-" => {}
+            "+" => value.push_str(&"\n".repeat(trailing + 1)),
+            _ if content > 0 => value.push('\n'),
+            _ => {}
+        }
+        Ok(Value::String(value))
+    }
+}
+
+pub fn parse(src: &str) -> Result<Table> {
+    let mut parser = Parser {
+        lines: src.lines().collect(),
+        pos: 0,
+        column: None,
+    };
+    if let Some((_, "---")) = parser.peek()? {
+        parser.advance();
+    }
+    let value = match parser.peek()? {
+        Some((column, _)) => parser.block(column)?,
+        None => return Ok(Table::new()),
+    };
+    match parser.peek()? {
+        Some((_, "...")) | None => {}
+        Some((_, text)) => return parser.error(format!("unexpected '{}'", text)),
+    }
+    match value {
+        Value::Table(table) => Ok(table),
+        v => Err(ParseError {
+            line: 1,
+            message: format!("expected mapping at top level but got {}", v.type_name()),
+        }),
+    }
+}
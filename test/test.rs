@@ -1,8 +1,23 @@
+// Keep the original tests as they were written; newer clippy only complains about their style
+#![allow(
+    clippy::assertions_on_constants,
+    clippy::extra_unused_lifetimes,
+    clippy::ineffective_open_options,
+    clippy::iter_nth_zero,
+    clippy::needless_borrow,
+    clippy::needless_borrows_for_generic_args,
+    clippy::needless_return,
+    clippy::useless_format
+)]
+
 #[macro_use]
 extern crate lazy_static;
 extern crate libc;
 extern crate semver;
 
+#[path = "../build/ci.rs"]
+mod ci;
+
 use semver::Version as SemVer;
 use std::fs::{File, OpenOptions};
 use std::io::{Read, Write};
@@ -52,6 +67,15 @@ fn tmpdir_for(name: &str) -> PathBuf {
     tmp
 }
 
+// Hooks are not installed in CI by default. Tests may be run in CI
+fn command<S: AsRef<ffi::OsStr>>(program: S) -> Command {
+    let mut cmd = Command::new(program);
+    for name in ci::CI_VARS {
+        cmd.env_remove(name);
+    }
+    cmd
+}
+
 fn open_cargo_toml(repo_dir: &Path) -> fs::File {
     OpenOptions::new()
         .write(true)
@@ -66,7 +90,7 @@ where
     S: AsRef<ffi::OsStr>,
     P: AsRef<Path>,
 {
-    let out = Command::new("cargo")
+    let out = command("cargo")
         .args(args)
         .current_dir(&project_root)
         .output()
@@ -116,6 +140,56 @@ fn get_hook_script(root: &Path, hook: &str) -> Option<String> {
     Some(s)
 }
 
+fn run_hook(root: &Path, hook: &str) -> Output {
+    command("sh")
+        .arg(hook_path(root, hook))
+        .current_dir(root)
+        .output()
+        .unwrap()
+}
+
+// Tools which are not installed are replaced with executables printing their name and arguments, so
+// that the commands run by the hook can be checked
+fn run_hook_with_fake_tools(root: &Path, hook: &str, tools: &[&str]) -> Output {
+    let bin = root.join("target").join("fake-tools");
+    fs::create_dir_all(&bin).unwrap();
+    for tool in tools {
+        let path = bin.join(tool);
+        fs::write(&path, format!("#!/bin/sh\necho \"{} $*\"\n", tool)).unwrap();
+        #[cfg(not(target_os = "windows"))]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+    }
+    let mut paths = vec![bin];
+    paths.extend(env::split_paths(&env::var_os("PATH").unwrap()));
+    command("sh")
+        .arg(hook_path(root, hook))
+        .env("PATH", env::join_paths(paths).unwrap())
+        .current_dir(root)
+        .output()
+        .unwrap()
+}
+
+// Updates the script hash of the hook script edited to simulate the one generated on another machine
+fn rehash_script(script: &str) -> String {
+    let i = script.find("# Script hash: ").unwrap();
+    let end = i + script[i..].find('\n').unwrap() + 1;
+    let shebang = &script[..script.find('\n').unwrap() + 1];
+    let hash = format!("{}{}", shebang, &script[end..])
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325u64, |hash, b| {
+            (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+        });
+    format!(
+        "{}# Script hash: {:016x}\n{}",
+        &script[..i],
+        hash,
+        &script[end..]
+    )
+}
+
 fn decrease_patch(mut ver: SemVer) -> SemVer {
     if ver.patch > 0 {
         ver.patch -= 1;
@@ -131,114 +205,4223 @@ fn decrease_patch(mut ver: SemVer) -> SemVer {
         ver.major -= 1;
         return ver;
     }
-    unreachable!();
+    unreachable!();
+}
+
+#[test]
+fn default_behavior() {
+    let root = cargo_project_for("default");
+    run_cargo(&root, &["test"]).unwrap();
+    let script = get_hook_script(&root, "pre-push").unwrap();
+
+    assert_eq!(script.lines().nth(0).unwrap(), "#!/bin/sh");
+    assert!(script
+        .lines()
+        .nth(2)
+        .unwrap()
+        .contains(format!("set by cargo-husky v{}", env!("CARGO_PKG_VERSION")).as_str()));
+    assert_eq!(
+        script.lines().filter(|l| *l == "cargo test --all").count(),
+        1
+    );
+    assert!(script.lines().all(|l| !l.contains("cargo clippy")));
+
+    assert_eq!(get_hook_script(&root, "pre-commit"), None);
+}
+
+#[test]
+#[cfg(not(target_os = "windows"))]
+fn hook_file_is_executable() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let root = cargo_project_for("unit-permission");
+    run_cargo(&root, &["test"]).unwrap();
+
+    let prepush_path = hook_path(&root, "pre-push");
+    let mode = File::open(&prepush_path)
+        .unwrap()
+        .metadata()
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o555, 0o555);
+}
+
+#[test]
+fn change_features() {
+    let root = cargo_project_for("features");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"precommit-hook\", \"run-cargo-clippy\", \"run-cargo-check\", \"run-cargo-fmt\"]"
+    ).unwrap();
+    run_cargo(&root, &["test"]).unwrap();
+
+    assert_eq!(get_hook_script(&root, "pre-push"), None);
+
+    let script = get_hook_script(&root, "pre-commit").unwrap();
+    assert!(script.lines().all(|l| l != "cargo test"));
+    assert_eq!(
+        script
+            .lines()
+            .filter(|l| *l == "cargo clippy -- -D warnings")
+            .count(),
+        1
+    );
+    assert_eq!(
+        script
+            .lines()
+            .filter(|l| *l == "cargo check --all-targets")
+            .count(),
+        1
+    );
+    assert_eq!(
+        script
+            .lines()
+            .filter(|l| *l == "cargo fmt -- --check")
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn change_features_using_run_for_all() {
+    let root = cargo_project_for("features_using_run_for_all");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"precommit-hook\", \"run-for-all\", \"run-cargo-test\", \"run-cargo-check\", \"run-cargo-clippy\", \"run-cargo-fmt\"]"
+    ).unwrap();
+    run_cargo(&root, &["test"]).unwrap();
+
+    assert_eq!(get_hook_script(&root, "pre-push"), None);
+
+    let script = get_hook_script(&root, "pre-commit").unwrap();
+    assert_eq!(
+        script.lines().filter(|l| *l == "cargo test --all").count(),
+        1
+    );
+    assert_eq!(
+        script
+            .lines()
+            .filter(|l| *l == "cargo clippy --all -- -D warnings")
+            .count(),
+        1
+    );
+    assert_eq!(
+        script
+            .lines()
+            .filter(|l| *l == "cargo check --all-targets --all")
+            .count(),
+        1
+    );
+    assert_eq!(
+        script
+            .lines()
+            .filter(|l| *l == "cargo fmt --all -- --check")
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn other_hooks() {
+    let root = cargo_project_for("other-hooks");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"commit-msg-hook\", \"prepare-commit-msg-hook\", \"postcheckout-hook\", \"prerebase-hook\", \"postcommit-hook\", \"premergecommit-hook\", \"applypatch-msg-hook\", \"preapplypatch-hook\", \"postapplypatch-hook\", \"postrewrite-hook\", \"run-cargo-test\"]"
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    assert_eq!(get_hook_script(&root, "pre-push"), None);
+
+    for hook in &[
+        "commit-msg",
+        "prepare-commit-msg",
+        "post-checkout",
+        "pre-rebase",
+        "post-commit",
+        "pre-merge-commit",
+        "applypatch-msg",
+        "pre-applypatch",
+        "post-applypatch",
+        "post-rewrite",
+    ] {
+        let script = get_hook_script(&root, hook).unwrap();
+        assert!(script
+            .lines()
+            .nth(2)
+            .unwrap()
+            .contains(format!("set by cargo-husky v{}", env!("CARGO_PKG_VERSION")).as_str()));
+        assert_eq!(script.lines().filter(|l| *l == "cargo test").count(), 1);
+    }
+}
+
+#[test]
+fn all_hooks() {
+    let root = cargo_project_for("all-hooks");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"all-hooks\", \"run-cargo-check\"]"
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    for hook in &[
+        "pre-push",
+        "pre-commit",
+        "post-merge",
+        "commit-msg",
+        "prepare-commit-msg",
+        "post-checkout",
+        "pre-rebase",
+        "post-commit",
+        "pre-merge-commit",
+        "applypatch-msg",
+        "pre-applypatch",
+        "post-applypatch",
+        "post-rewrite",
+    ] {
+        let script = get_hook_script(&root, hook).unwrap();
+        assert_eq!(
+            script
+                .lines()
+                .filter(|l| *l == "cargo check --all-targets")
+                .count(),
+            1
+        );
+    }
+}
+
+#[test]
+fn commands_per_hook() {
+    let root = cargo_project_for("commands-per-hook");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        r#"default-features = false
+features = ["prepush-hook", "precommit-hook", "run-cargo-test", "run-cargo-clippy"]
+
+[package.metadata.husky.hooks]
+pre-commit = ["fmt"]"#
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-commit").unwrap();
+    assert_eq!(
+        script
+            .lines()
+            .filter(|l| *l == "cargo fmt -- --check")
+            .count(),
+        1
+    );
+    assert!(script.lines().all(|l| l != "cargo test"));
+    assert!(script.lines().all(|l| !l.contains("cargo clippy")));
+
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert_eq!(script.lines().filter(|l| *l == "cargo test").count(), 1);
+    assert_eq!(
+        script
+            .lines()
+            .filter(|l| *l == "cargo clippy -- -D warnings")
+            .count(),
+        1
+    );
+    assert!(script.lines().all(|l| !l.contains("cargo fmt")));
+}
+
+#[test]
+fn hooks_enabled_by_config() {
+    let root = cargo_project_for("hooks-enabled-by-config");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "\n[package.metadata.husky.hooks]\npre-commit = [\"fmt\"]\ncommit-msg = []"
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    // pre-push hook is still enabled by default features
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert_eq!(
+        script.lines().filter(|l| *l == "cargo test --all").count(),
+        1
+    );
+    let script = get_hook_script(&root, "pre-commit").unwrap();
+    assert_eq!(
+        script
+            .lines()
+            .filter(|l| *l == "cargo fmt --all -- --check")
+            .count(),
+        1
+    );
+    assert!(get_hook_script(&root, "commit-msg").is_some());
+    assert_eq!(get_hook_script(&root, "post-merge"), None);
+
+    let out = run_hook(&root, "commit-msg");
+    assert!(out.status.success(), "{:?}", out);
+    let out = run_hook(&root, "pre-commit");
+    assert!(out.status.success(), "{:?}", out);
+    fs::write(root.join("src").join("lib.rs"), "pub fn one()->i32{1}\n").unwrap();
+    let out = run_hook(&root, "pre-commit");
+    assert!(!out.status.success(), "{:?}", out);
+}
+
+#[test]
+fn date_time_values_in_manifest() {
+    let root = cargo_project_for("date-time-values");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "\n[package.metadata]\nreleased = 1979-05-27\nupdated = 1979-05-27T07:32:00Z\nbuilds = [1979-05-27 07:32:00.999, 00:32:00] # comment\n\n[package.metadata.husky.hooks]\npre-commit = [\"fmt\"]"
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-commit").unwrap();
+    assert_eq!(
+        script
+            .lines()
+            .filter(|l| *l == "cargo fmt --all -- --check")
+            .count(),
+        1
+    );
+    let out = run_hook(&root, "pre-commit");
+    assert!(out.status.success(), "{:?}", out);
+}
+
+#[test]
+fn unknown_command_in_config() {
+    let root = cargo_project_for("unknown-command-in-config");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "\n[package.metadata.husky.hooks]\npre-push = [\"test\", \"fmtt\"]"
+    )
+    .unwrap();
+
+    let err = run_cargo(&root, ["test"]).unwrap_err();
+    assert!(
+        err.contains("'package.metadata.husky.hooks.pre-push'")
+            && err.contains("unknown command 'fmtt'"),
+        "Unexpected output on `cargo test`: {}",
+        err
+    );
+    assert_eq!(get_hook_script(&root, "pre-push"), None);
+}
+
+#[test]
+fn run_cargo_audit() {
+    let root = cargo_project_for("run-cargo-audit");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"prepush-hook\", \"run-cargo-audit\"]"
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert_eq!(
+        script
+            .lines()
+            .filter(|l| *l == "if cargo audit --version >/dev/null 2>&1; then")
+            .count(),
+        1
+    );
+    assert_eq!(
+        script.lines().filter(|l| *l == "    cargo audit").count(),
+        1
+    );
+
+    if run_cargo(&root, ["audit", "--version"]).is_err() {
+        // Hook should not fail when cargo-audit is not installed
+        let out = run_hook(&root, "pre-push");
+        assert!(out.status.success(), "{:?}", out);
+        assert!(str::from_utf8(&out.stdout)
+            .unwrap()
+            .contains("cargo-audit is not installed"));
+    }
+}
+
+#[test]
+fn run_cargo_deny() {
+    let root = cargo_project_for("run-cargo-deny");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"prepush-hook\", \"run-cargo-deny\"]\n\n[package.metadata.husky.commands.deny]\nchecks = [\"advisories\", \"bans\"]"
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert_eq!(
+        script
+            .lines()
+            .filter(|l| *l == "    cargo deny check advisories bans")
+            .count(),
+        1
+    );
+    assert!(script.lines().all(|l| l != "cargo test"));
+
+    let out = run_hook_with_fake_tools(&root, "pre-push", &["cargo-deny"]);
+    assert!(out.status.success(), "{:?}", out);
+    let stdout = str::from_utf8(&out.stdout).unwrap();
+    assert!(
+        stdout.contains("\ncargo-deny deny check advisories bans\n"),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn unknown_deny_check_in_config() {
+    let root = cargo_project_for("unknown-deny-check");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"prepush-hook\", \"run-cargo-deny\"]\n\n[package.metadata.husky.commands.deny]\nchecks = [\"license\"]"
+    )
+    .unwrap();
+
+    let err = run_cargo(&root, ["test"]).unwrap_err();
+    assert!(
+        err.contains("'package.metadata.husky.commands.deny.checks'")
+            && err.contains("unknown value 'license'"),
+        "Unexpected output on `cargo test`: {}",
+        err
+    );
+    assert_eq!(get_hook_script(&root, "pre-push"), None);
+}
+
+#[test]
+fn run_cargo_doc() {
+    let root = cargo_project_for("run-cargo-doc");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"prepush-hook\", \"run-cargo-doc\"]"
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert_eq!(
+        script
+            .lines()
+            .filter(|l| *l == "RUSTDOCFLAGS=\"-D warnings\" cargo doc --no-deps")
+            .count(),
+        1
+    );
+
+    let out = run_hook(&root, "pre-push");
+    assert!(out.status.success(), "{:?}", out);
+
+    // Broken intra-doc link makes the hook fail
+    let mut lib_rs = OpenOptions::new()
+        .append(true)
+        .open(root.join("src").join("lib.rs"))
+        .unwrap();
+    writeln!(
+        lib_rs,
+        "\n/// See [`DoesNotExist`]\npub fn documented() {{}}"
+    )
+    .unwrap();
+    let out = run_hook(&root, "pre-push");
+    assert!(!out.status.success(), "{:?}", out);
+}
+
+#[test]
+fn use_cargo_nextest() {
+    let root = cargo_project_for("use-cargo-nextest");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "features = [\"precommit-hook\", \"use-cargo-nextest\"]"
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    for hook in &["pre-push", "pre-commit"] {
+        let script = get_hook_script(&root, hook).unwrap();
+        assert_eq!(
+            script
+                .lines()
+                .filter(|l| *l == "cargo nextest run --all")
+                .count(),
+            1
+        );
+        assert!(script.lines().all(|l| l != "cargo test --all"));
+
+        let out = run_hook_with_fake_tools(&root, hook, &["cargo-nextest"]);
+        assert!(out.status.success(), "{:?}", out);
+        let stdout = str::from_utf8(&out.stdout).unwrap();
+        assert!(
+            stdout.contains("\ncargo-nextest nextest run --all\n"),
+            "{}",
+            stdout
+        );
+    }
+}
+
+#[test]
+fn run_cargo_hack() {
+    let root = cargo_project_for("run-cargo-hack");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"prepush-hook\", \"run-cargo-hack\"]"
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert_eq!(
+        script
+            .lines()
+            .filter(|l| *l == "    cargo hack check --each-feature")
+            .count(),
+        1
+    );
+    let out = run_hook_with_fake_tools(&root, "pre-push", &["cargo-hack"]);
+    assert!(out.status.success(), "{:?}", out);
+    let stdout = str::from_utf8(&out.stdout).unwrap();
+    assert!(
+        stdout.contains("\ncargo-hack hack check --each-feature\n"),
+        "{}",
+        stdout
+    );
+
+    // Configured arguments replace the default ones
+    let root = cargo_project_for("run-cargo-hack-args");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"prepush-hook\", \"run-cargo-hack\"]\n\n[package.metadata.husky.commands.hack]\nargs = [\"--feature-powerset\", \"--depth\", \"2\"]"
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert_eq!(
+        script
+            .lines()
+            .filter(|l| *l == "    cargo hack check --feature-powerset --depth 2")
+            .count(),
+        1
+    );
+    let out = run_hook_with_fake_tools(&root, "pre-push", &["cargo-hack"]);
+    assert!(out.status.success(), "{:?}", out);
+    let stdout = str::from_utf8(&out.stdout).unwrap();
+    assert!(
+        stdout.contains("\ncargo-hack hack check --feature-powerset --depth 2\n"),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn run_unused_deps() {
+    let root = cargo_project_for("run-unused-deps");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"prepush-hook\", \"run-unused-deps\"]"
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert_eq!(
+        script.lines().filter(|l| *l == "    cargo machete").count(),
+        1
+    );
+    let out = run_hook_with_fake_tools(&root, "pre-push", &["cargo-machete"]);
+    assert!(out.status.success(), "{:?}", out);
+    let stdout = str::from_utf8(&out.stdout).unwrap();
+    assert!(stdout.contains("\ncargo-machete machete\n"), "{}", stdout);
+
+    let root = cargo_project_for("run-unused-deps-udeps");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"prepush-hook\", \"run-unused-deps\"]\n\n[package.metadata.husky.commands.unused-deps]\ntool = \"udeps\"\ntoolchain = \"nightly-2024-06-01\""
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert_eq!(
+        script
+            .lines()
+            .filter(|l| *l == "    cargo +nightly-2024-06-01 udeps")
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn run_cargo_outdated_on_post_merge() {
+    let root = cargo_project_for("run-cargo-outdated");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"prepush-hook\", \"postmerge-hook\", \"run-cargo-test\", \"run-cargo-outdated\"]"
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let line = "    cargo outdated --exit-code 0";
+    let script = get_hook_script(&root, "post-merge").unwrap();
+    assert_eq!(script.lines().filter(|l| *l == line).count(), 1);
+    assert!(script.lines().any(|l| l == "cargo test"));
+
+    // Only post-merge hook runs cargo-outdated by default
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert!(script.lines().all(|l| l != line));
+
+    let out = run_hook_with_fake_tools(&root, "post-merge", &["cargo-outdated"]);
+    assert!(out.status.success(), "{:?}", out);
+    let stdout = str::from_utf8(&out.stdout).unwrap();
+    assert!(
+        stdout.contains("\ncargo-outdated outdated --exit-code 0\n"),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn run_cargo_semver_checks_on_pre_push() {
+    let root = cargo_project_for("run-cargo-semver-checks");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"prepush-hook\", \"precommit-hook\", \"run-cargo-semver-checks\"]"
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let line = "    cargo semver-checks check-release";
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert_eq!(script.lines().filter(|l| *l == line).count(), 1);
+    let script = get_hook_script(&root, "pre-commit").unwrap();
+    assert!(script.lines().all(|l| l != line));
+
+    if run_cargo(&root, ["semver-checks", "--version"]).is_err() {
+        let out = run_hook(&root, "pre-push");
+        assert!(out.status.success(), "{:?}", out);
+        assert!(str::from_utf8(&out.stdout)
+            .unwrap()
+            .contains("cargo-semver-checks is not installed"));
+    }
+}
+
+#[test]
+fn run_coverage() {
+    let root = cargo_project_for("run-coverage");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"prepush-hook\", \"run-coverage\"]"
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert_eq!(
+        script
+            .lines()
+            .filter(|l| *l == "    cargo llvm-cov")
+            .count(),
+        1
+    );
+    let out = run_hook_with_fake_tools(&root, "pre-push", &["cargo-llvm-cov"]);
+    assert!(out.status.success(), "{:?}", out);
+    let stdout = str::from_utf8(&out.stdout).unwrap();
+    assert!(stdout.contains("\ncargo-llvm-cov llvm-cov\n"), "{}", stdout);
+
+    let root = cargo_project_for("run-coverage-threshold");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"prepush-hook\", \"run-coverage\"]\n\n[package.metadata.husky.commands.coverage]\ntool = \"tarpaulin\"\nthreshold = 72.5"
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert_eq!(
+        script
+            .lines()
+            .filter(|l| *l == "    cargo tarpaulin --fail-under 72.5")
+            .count(),
+        1
+    );
+    let out = run_hook_with_fake_tools(&root, "pre-push", &["cargo-tarpaulin"]);
+    assert!(out.status.success(), "{:?}", out);
+    let stdout = str::from_utf8(&out.stdout).unwrap();
+    assert!(
+        stdout.contains("\ncargo-tarpaulin tarpaulin --fail-under 72.5\n"),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn invalid_coverage_threshold() {
+    let root = cargo_project_for("invalid-coverage-threshold");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"prepush-hook\", \"run-coverage\"]\n\n[package.metadata.husky.commands.coverage]\nthreshold = 120"
+    )
+    .unwrap();
+
+    let err = run_cargo(&root, ["test"]).unwrap_err();
+    assert!(
+        err.contains("'package.metadata.husky.commands.coverage.threshold'")
+            && err.contains("percentage must be in 0..100 but got 120"),
+        "Unexpected output on `cargo test`: {}",
+        err
+    );
+    assert_eq!(get_hook_script(&root, "pre-push"), None);
+}
+
+#[test]
+fn run_cargo_miri_on_pre_push() {
+    let root = cargo_project_for("run-cargo-miri");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"prepush-hook\", \"precommit-hook\", \"run-cargo-miri\"]\n\n[package.metadata.husky.commands.miri]\nargs = [\"--test\", \"unsafe_code\"]"
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let line = "    cargo +nightly miri test --test unsafe_code";
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert_eq!(script.lines().filter(|l| *l == line).count(), 1);
+    let script = get_hook_script(&root, "pre-commit").unwrap();
+    assert!(script.lines().all(|l| l != line));
+}
+
+#[test]
+fn run_cargo_bench_on_pre_push() {
+    let root = cargo_project_for("run-cargo-bench");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"prepush-hook\", \"precommit-hook\", \"run-cargo-bench\"]\n\n[package.metadata.husky.commands.bench]\nthreshold = 10\nbaseline = \"main\""
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    for line in &[
+        "    cargo bench -- --save-baseline main",
+        "    cargo bench -- --baseline main",
+        "        if [ -n \"$change\" ] && awk \"BEGIN { exit !($change * 100 > 10) }\"; then",
+    ] {
+        assert_eq!(
+            script.lines().filter(|l| l == line).count(),
+            1,
+            "{}",
+            script
+        );
+    }
+    let script = get_hook_script(&root, "pre-commit").unwrap();
+    assert!(script.lines().all(|l| !l.contains("cargo bench")));
+}
+
+#[test]
+fn run_msrv_check() {
+    let root = cargo_project_for("run-msrv-check");
+    let manifest = root.join("Cargo.toml");
+    let src = fs::read_to_string(&manifest).unwrap();
+    fs::write(
+        &manifest,
+        src.replacen("[package]\n", "[package]\nrust-version = \"1.85\"\n", 1),
+    )
+    .unwrap();
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"prepush-hook\", \"run-msrv-check\"]"
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert_eq!(
+        script
+            .lines()
+            .filter(|l| *l == "    cargo +1.85 check")
+            .count(),
+        1
+    );
+
+    // Hook should not fail when the toolchain is not installed
+    let out = run_hook(&root, "pre-push");
+    assert!(out.status.success(), "{:?}", out);
+    let stdout = str::from_utf8(&out.stdout).unwrap();
+    assert!(
+        stdout.contains("+cargo +1.85 check")
+            || stdout.contains("Rust 1.85 toolchain is not installed"),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn run_target_check() {
+    let root = cargo_project_for("run-target-check");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "features = [\"run-target-check\"]\n\n[package.metadata.husky.commands.target-check]\ntargets = [\"wasm32-unknown-unknown\", \"wasm32-wasip1\"]"
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    for line in &[
+        "    cargo check --target wasm32-unknown-unknown --all",
+        "    cargo check --target wasm32-wasip1 --all",
+    ] {
+        assert_eq!(
+            script.lines().filter(|l| l == line).count(),
+            1,
+            "{}",
+            script
+        );
+    }
+
+    // Targets whose standard library is not installed are skipped
+    let out = run_hook(&root, "pre-push");
+    assert!(out.status.success(), "{:?}", out);
+    let installed = command("rustup")
+        .args(["target", "list", "--installed"])
+        .output()
+        .unwrap();
+    let installed = str::from_utf8(&installed.stdout).unwrap();
+    let stdout = str::from_utf8(&out.stdout).unwrap();
+    for target in &["wasm32-unknown-unknown", "wasm32-wasip1"] {
+        if !installed.lines().any(|l| l == *target) {
+            let msg = format!("Target {} is not installed", target);
+            assert!(stdout.contains(&msg), "{}", stdout);
+        }
+    }
+}
+
+#[test]
+fn run_no_std_check() {
+    let root = cargo_project_for("run-no-std-check");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"prepush-hook\", \"run-no-std-check\"]\n\n[package.metadata.husky.commands.no-std-check]\nfeatures = [\"alloc\", \"serde\"]\n\n[features]\nalloc = []\nserde = []"
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert_eq!(
+        script
+            .lines()
+            .filter(|l| *l == "cargo check --no-default-features --features alloc,serde")
+            .count(),
+        1
+    );
+
+    let out = run_hook(&root, "pre-push");
+    assert!(out.status.success(), "{:?}", out);
+    let stdout = str::from_utf8(&out.stdout).unwrap();
+    assert!(
+        stdout.contains("+cargo check --no-default-features --features alloc,serde"),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn no_std_check_features_are_quoted() {
+    let root = cargo_project_for("no-std-check-quoted");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"prepush-hook\", \"run-no-std-check\"]\n\n[package.metadata.husky.commands.no-std-check]\nfeatures = [\"alloc\", \"$(touch pwned)\"]"
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    let cmd = "cargo check --no-default-features --features 'alloc,$(touch pwned)'";
+    assert_eq!(script.lines().filter(|l| *l == cmd).count(), 1);
+
+    // The feature does not exist but it must not be run as a command
+    let out = run_hook(&root, "pre-push");
+    assert!(!out.status.success(), "{:?}", out);
+    assert!(!root.join("pwned").exists());
+}
+
+#[test]
+fn run_typos_on_pre_commit() {
+    let root = cargo_project_for("run-typos");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"prepush-hook\", \"precommit-hook\", \"run-typos\"]"
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-commit").unwrap();
+    assert_eq!(
+        script
+            .lines()
+            .filter(|l| l.starts_with("    git diff --cached --name-only")
+                && l.ends_with("typos -- \"$@\"' typos"))
+            .count(),
+        1,
+        "{}",
+        script
+    );
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert!(script.lines().all(|l| !l.contains("typos --version")));
+
+    let out = run_hook(&root, "pre-commit");
+    assert!(out.status.success(), "{:?}", out);
+}
+
+#[test]
+fn run_cargo_sort_on_pre_commit() {
+    let root = cargo_project_for("run-cargo-sort");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "features = [\"precommit-hook\", \"run-cargo-sort\"]"
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let line = "    cargo sort --check --workspace";
+    let script = get_hook_script(&root, "pre-commit").unwrap();
+    assert_eq!(script.lines().filter(|l| *l == line).count(), 1);
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert!(script.lines().all(|l| l != line));
+
+    let out = run_hook_with_fake_tools(&root, "pre-commit", &["cargo-sort"]);
+    assert!(out.status.success(), "{:?}", out);
+    let stdout = str::from_utf8(&out.stdout).unwrap();
+    assert!(
+        stdout.contains("\ncargo-sort sort --check --workspace\n"),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn run_taplo_fmt() {
+    let root = cargo_project_for("run-taplo-fmt");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"prepush-hook\", \"run-taplo-fmt\"]"
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert_eq!(
+        script
+            .lines()
+            .filter(|l| *l == "    taplo fmt --check")
+            .count(),
+        1
+    );
+
+    let out = run_hook_with_fake_tools(&root, "pre-push", &["taplo"]);
+    assert!(out.status.success(), "{:?}", out);
+    let stdout = str::from_utf8(&out.stdout).unwrap();
+    assert!(stdout.contains("\ntaplo fmt --check\n"), "{}", stdout);
+}
+
+#[test]
+fn run_cargo_vet() {
+    let root = cargo_project_for("run-cargo-vet");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"prepush-hook\", \"run-cargo-vet\"]"
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert_eq!(script.lines().filter(|l| *l == "    cargo vet").count(), 1);
+
+    if run_cargo(&root, ["vet", "--version"]).is_err() {
+        let out = run_hook(&root, "pre-push");
+        assert!(out.status.success(), "{:?}", out);
+        assert!(str::from_utf8(&out.stdout)
+            .unwrap()
+            .contains("cargo-vet is not installed"));
+    }
+}
+
+#[test]
+fn run_cargo_license() {
+    let root = cargo_project_for("run-cargo-license");
+    let manifest = root.join("Cargo.toml");
+    let src = fs::read_to_string(&manifest).unwrap();
+    fs::write(
+        &manifest,
+        src.replacen(
+            "[package]\n",
+            "[package]\nlicense = \"MIT OR Apache-2.0\"\n",
+            1,
+        ),
+    )
+    .unwrap();
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"prepush-hook\", \"run-cargo-license\"]\n\n[package.metadata.husky.commands.license]\nallowlist = \"licenses.txt\""
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert_eq!(
+        script
+            .lines()
+            .filter(|l| *l == "elif [ ! -f 'licenses.txt' ]; then")
+            .count(),
+        1
+    );
+
+    if run_cargo(&root, ["license", "--help"]).is_err() {
+        let out = run_hook(&root, "pre-push");
+        assert!(out.status.success(), "{:?}", out);
+        assert!(str::from_utf8(&out.stdout)
+            .unwrap()
+            .contains("cargo-license is not installed"));
+        return;
+    }
+
+    let out = run_hook(&root, "pre-push");
+    assert!(!out.status.success(), "{:?}", out);
+
+    fs::write(
+        root.join("licenses.txt"),
+        "# Allowed licenses\nBSD-3-Clause\n",
+    )
+    .unwrap();
+    let out = run_hook(&root, "pre-push");
+    assert!(!out.status.success(), "{:?}", out);
+    assert!(str::from_utf8(&out.stdout)
+        .unwrap()
+        .contains("run-cargo-license 0.1.0: Apache-2.0 OR MIT"));
+
+    fs::write(root.join("licenses.txt"), "BSD-3-Clause\nApache-2.0\n").unwrap();
+    let out = run_hook(&root, "pre-push");
+    assert!(out.status.success(), "{:?}", out);
+}
+
+#[test]
+fn run_cargo_geiger() {
+    let root = cargo_project_for("run-cargo-geiger");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"prepush-hook\", \"run-cargo-geiger\"]\n\n[package.metadata.husky.commands.geiger]\nbudget = 10"
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert_eq!(
+        script
+            .lines()
+            .filter(|l| *l == "    if [ \"$count\" -gt 10 ]; then")
+            .count(),
+        1
+    );
+
+    let out = run_hook(&root, "pre-push");
+    assert!(out.status.success(), "{:?}", out);
+}
+
+#[test]
+fn negative_geiger_budget() {
+    let root = cargo_project_for("negative-geiger-budget");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "features = [\"run-cargo-geiger\"]\n\n[package.metadata.husky.commands.geiger]\nbudget = -1"
+    )
+    .unwrap();
+
+    let err = run_cargo(&root, ["test"]).unwrap_err();
+    assert!(
+        err.contains("'package.metadata.husky.commands.geiger.budget'")
+            && err.contains("expected non-negative integer but got -1"),
+        "Unexpected output on `cargo test`: {}",
+        err
+    );
+}
+
+#[test]
+fn doc_tests_only() {
+    let root = cargo_project_for("doc-tests-only");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(cargo_toml, "features = [\"doc-tests-only\"]").unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert_eq!(
+        script
+            .lines()
+            .filter(|l| *l == "cargo test --doc --all")
+            .count(),
+        1
+    );
+    assert!(script.lines().all(|l| l != "cargo test --all"));
+
+    let out = run_hook(&root, "pre-push");
+    assert!(out.status.success(), "{:?}", out);
+    let stderr = str::from_utf8(&out.stderr).unwrap();
+    assert!(stderr.contains("Doc-tests doc_tests_only"), "{}", stderr);
+    assert!(!stderr.contains("unittests"), "{}", stderr);
+}
+
+#[test]
+fn cargo_test_args() {
+    let root = cargo_project_for("cargo-test-args");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "\n[package.metadata.husky.commands.test]\nargs = [\"--all-features\", \"--release\", \"--\", \"--skip\", \"slow_\"]"
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert_eq!(
+        script
+            .lines()
+            .filter(|l| *l == "cargo test --all --all-features --release -- --skip slow_")
+            .count(),
+        1
+    );
+
+    // Arguments after `--` are passed to the test binary
+    fs::write(
+        root.join("src").join("lib.rs"),
+        "#[test]\nfn slow_test() {\n    panic!();\n}\n",
+    )
+    .unwrap();
+    let out = run_hook(&root, "pre-push");
+    assert!(out.status.success(), "{:?}", out);
+    let stderr = str::from_utf8(&out.stderr).unwrap();
+    assert!(stderr.contains("`release` profile"), "{}", stderr);
+}
+
+#[test]
+fn command_args_are_quoted() {
+    let root = cargo_project_for("command-args-quoted");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "\n[package.metadata.husky.commands.test]\nargs = [\"--features\", \"a b\", \"--\", \"--skip\", \"x;echo INJECTED\"]\n\n[features]\na = []\nb = []"
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    let line = "cargo test --all --features 'a b' -- --skip 'x;echo INJECTED'";
+    assert!(script.lines().any(|l| l == line), "{}", script);
+
+    let out = run_hook(&root, "pre-push");
+    assert!(out.status.success(), "{:?}", out);
+    let stdout = str::from_utf8(&out.stdout).unwrap();
+    assert!(stdout.contains(&format!("+{}\n", line)), "{}", stdout);
+    assert!(!stdout.lines().any(|l| l == "INJECTED"), "{}", stdout);
+}
+
+#[test]
+fn cargo_clippy_args() {
+    let root = cargo_project_for("cargo-clippy-args");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"prepush-hook\", \"run-cargo-clippy\"]\n\n[package.metadata.husky.commands.clippy]\nargs = [\"--all-targets\", \"--all-features\", \"--\", \"-D\", \"warnings\"]"
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert_eq!(
+        script
+            .lines()
+            .filter(|l| *l == "cargo clippy --all-targets --all-features -- -D warnings")
+            .count(),
+        1
+    );
+
+    let out = run_hook(&root, "pre-push");
+    assert!(out.status.success(), "{:?}", out);
+    // Warning in test code is only found with `--all-targets`
+    fs::write(
+        root.join("src").join("lib.rs"),
+        "#[cfg(test)]\nmod tests {\n    #[test]\n    fn unused() {\n        let x = 1;\n    }\n}\n",
+    )
+    .unwrap();
+    let out = run_hook(&root, "pre-push");
+    assert!(!out.status.success(), "{:?}", out);
+}
+
+#[test]
+fn cargo_clippy_fix_on_pre_commit() {
+    let root = cargo_project_for("cargo-clippy-fix");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"prepush-hook\", \"precommit-hook\", \"run-cargo-clippy\"]\n\n[package.metadata.husky.commands.clippy]\nfix = true"
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let fix = "cargo clippy --fix --allow-staged --allow-dirty";
+    let script = get_hook_script(&root, "pre-commit").unwrap();
+    assert_eq!(script.lines().filter(|l| *l == fix).count(), 1);
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert!(script.lines().all(|l| l != fix));
+
+    // Fixable lint is fixed and the fix is staged
+    fs::write(
+        root.join("src").join("lib.rs"),
+        "pub fn one() -> i32 {\n    return 1;\n}\n",
+    )
+    .unwrap();
+    let git = |args: &[&str]| {
+        command("git")
+            .args(args)
+            .current_dir(&root)
+            .output()
+            .unwrap()
+    };
+    git(&["add", "src/lib.rs"]);
+    let out = run_hook(&root, "pre-commit");
+    assert!(out.status.success(), "{:?}", out);
+    let staged = git(&["show", ":src/lib.rs"]);
+    let staged = str::from_utf8(&staged.stdout).unwrap();
+    assert!(!staged.contains("return"), "{}", staged);
+}
+
+#[test]
+fn cargo_clippy_fix_skips_partially_staged_files() {
+    let root = cargo_project_for("cargo-clippy-fix-partial");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"precommit-hook\", \"run-cargo-clippy\"]\n\n[package.metadata.husky.commands.clippy]\nfix = true"
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let src = root.join("src");
+    let lib = "pub mod two;\npub fn one() -> i32 {\n    return 1;\n}\n";
+    fs::write(src.join("lib.rs"), lib).unwrap();
+    fs::write(
+        src.join("two.rs"),
+        "pub fn two() -> i32 {\n    return 2;\n}\n",
+    )
+    .unwrap();
+    let git = |args: &[&str]| {
+        command("git")
+            .args(args)
+            .current_dir(&root)
+            .output()
+            .unwrap()
+    };
+    git(&["add", "src/lib.rs", "src/two.rs"]);
+    fs::write(
+        src.join("lib.rs"),
+        format!("{}pub fn zero() -> i32 {{\n    0\n}}\n", lib),
+    )
+    .unwrap();
+
+    let out = run_hook(&root, "pre-commit");
+    assert!(out.status.success(), "{:?}", out);
+    let stderr = str::from_utf8(&out.stderr).unwrap();
+    assert!(
+        stderr.contains("Skipped staging src/lib.rs again since it has unstaged changes"),
+        "{}",
+        stderr
+    );
+
+    // Fully staged file is staged again after the fix
+    let staged = git(&["show", ":src/two.rs"]);
+    assert!(!str::from_utf8(&staged.stdout).unwrap().contains("return"));
+    // Unstaged changes of partially staged file are not added to the commit
+    let staged = git(&["show", ":src/lib.rs"]);
+    assert_eq!(str::from_utf8(&staged.stdout).unwrap(), lib);
+    let fixed = fs::read_to_string(src.join("lib.rs")).unwrap();
+    assert!(
+        !fixed.contains("return") && fixed.contains("fn zero"),
+        "{}",
+        fixed
+    );
+}
+
+#[test]
+fn cargo_fmt_fix_on_pre_commit() {
+    let root = cargo_project_for("cargo-fmt-fix");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"prepush-hook\", \"precommit-hook\", \"run-cargo-fmt\"]\n\n[package.metadata.husky.commands.fmt]\nfix = true"
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-commit").unwrap();
+    assert_eq!(script.lines().filter(|l| *l == "cargo fmt").count(), 1);
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert_eq!(
+        script
+            .lines()
+            .filter(|l| *l == "cargo fmt -- --check")
+            .count(),
+        1
+    );
+
+    // Reformatted file is staged
+    fs::write(root.join("src").join("lib.rs"), "pub fn one()->i32{1}\n").unwrap();
+    let git = |args: &[&str]| {
+        command("git")
+            .args(args)
+            .current_dir(&root)
+            .output()
+            .unwrap()
+    };
+    git(&["add", "src/lib.rs"]);
+    let out = run_hook(&root, "pre-commit");
+    assert!(out.status.success(), "{:?}", out);
+    let staged = git(&["show", ":src/lib.rs"]);
+    assert_eq!(
+        str::from_utf8(&staged.stdout).unwrap(),
+        "pub fn one() -> i32 {\n    1\n}\n"
+    );
+}
+
+#[test]
+fn cargo_fmt_fix_skips_partially_staged_files() {
+    let root = cargo_project_for("cargo-fmt-fix-partial");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"precommit-hook\", \"run-cargo-fmt\"]\n\n[package.metadata.husky.commands.fmt]\nfix = true"
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let src = root.join("src");
+    let lib = "pub mod two;\npub fn one()->i32{1}\n";
+    fs::write(src.join("lib.rs"), lib).unwrap();
+    fs::write(src.join("two.rs"), "pub fn two()->i32{2}\n").unwrap();
+    let git = |args: &[&str]| {
+        command("git")
+            .args(args)
+            .current_dir(&root)
+            .output()
+            .unwrap()
+    };
+    git(&["add", "src/lib.rs", "src/two.rs"]);
+    fs::write(
+        src.join("lib.rs"),
+        format!("{}pub fn zero()->i32{{0}}\n", lib),
+    )
+    .unwrap();
+
+    let out = run_hook(&root, "pre-commit");
+    assert!(out.status.success(), "{:?}", out);
+    let stderr = str::from_utf8(&out.stderr).unwrap();
+    assert!(
+        stderr.contains("Skipped staging src/lib.rs again since it has unstaged changes"),
+        "{}",
+        stderr
+    );
+
+    let staged = git(&["show", ":src/two.rs"]);
+    assert_eq!(
+        str::from_utf8(&staged.stdout).unwrap(),
+        "pub fn two() -> i32 {\n    2\n}\n"
+    );
+    let staged = git(&["show", ":src/lib.rs"]);
+    assert_eq!(str::from_utf8(&staged.stdout).unwrap(), lib);
+}
+
+#[test]
+fn toolchain_per_command() {
+    let root = cargo_project_for("toolchain-per-command");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "features = [\"run-cargo-check\", \"run-cargo-fmt\"]\n\n[package.metadata.husky.commands.fmt]\ntoolchain = \"nightly\"\n\n[package.metadata.husky.commands.check]\ntoolchain = \"1.70\""
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    for line in &[
+        "cargo test --all",
+        "cargo +1.70 check --all-targets --all",
+        "cargo +nightly fmt --all -- --check",
+    ] {
+        assert_eq!(
+            script.lines().filter(|l| l == line).count(),
+            1,
+            "{}",
+            script
+        );
+    }
+}
+
+#[test]
+fn toolchain_for_non_cargo_command() {
+    let root = cargo_project_for("toolchain-for-non-cargo-command");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "features = [\"run-typos\"]\n\n[package.metadata.husky.commands.typos]\ntoolchain = \"nightly\""
+    )
+    .unwrap();
+
+    let err = run_cargo(&root, ["test"]).unwrap_err();
+    assert!(
+        err.contains("'package.metadata.husky.commands.typos.toolchain'")
+            && err.contains("unknown setting for 'typos' command"),
+        "Unexpected output on `cargo test`: {}",
+        err
+    );
+}
+
+#[test]
+fn custom_commands() {
+    let root = cargo_project_for("custom-commands");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "\n[package.metadata.husky.hooks]\npre-push = [\"test\", \"hello\"]\n\n[package.metadata.husky.commands.hello]\nrun = \"echo 'hello, world' > hello.txt\""
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert_eq!(
+        script
+            .lines()
+            .filter(|l| *l == "echo 'hello, world' > hello.txt")
+            .count(),
+        1
+    );
+
+    let out = run_hook(&root, "pre-push");
+    assert!(out.status.success(), "{:?}", out);
+    assert!(str::from_utf8(&out.stdout)
+        .unwrap()
+        .contains("+echo 'hello, world' > hello.txt"));
+    assert_eq!(
+        fs::read_to_string(root.join("hello.txt")).unwrap(),
+        "hello, world\n"
+    );
+}
+
+#[test]
+fn custom_command_without_run() {
+    let root = cargo_project_for("custom-command-without-run");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "\n[package.metadata.husky.commands.hello]\nrn = \"echo hello\""
+    )
+    .unwrap();
+
+    let err = run_cargo(&root, ["test"]).unwrap_err();
+    assert!(
+        err.contains("'package.metadata.husky.commands.hello.rn'")
+            && err.contains("unknown setting for 'hello' command"),
+        "Unexpected output on `cargo test`: {}",
+        err
+    );
+}
+
+#[test]
+fn config_file() {
+    let root = cargo_project_for("config-file");
+    let mut f = File::create(root.join(".cargo-husky.toml")).unwrap();
+    writeln!(
+        f,
+        "[hooks]\npre-commit = [\"check\"]\n\n[commands.check]\ntoolchain = \"stable\""
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-commit").unwrap();
+    assert!(
+        script
+            .lines()
+            .any(|l| l == "cargo +stable check --all-targets --all"),
+        "{}",
+        script
+    );
+
+    let root = cargo_project_for("invalid-config-file");
+    let mut f = File::create(root.join(".cargo-husky.toml")).unwrap();
+    writeln!(f, "[hooks]\npre-commit = [\"fmtt\"]").unwrap();
+    let err = run_cargo(&root, ["test"]).unwrap_err();
+    assert!(
+        err.contains("'hooks.pre-commit'") && err.contains(".cargo-husky.toml"),
+        "Unexpected output on `cargo test`: {}",
+        err
+    );
+}
+
+#[test]
+fn config_in_both_cargo_toml_and_config_file() {
+    let root = cargo_project_for("config-in-both-places");
+    File::create(root.join(".cargo-husky.toml")).unwrap();
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "\n[package.metadata.husky.hooks]\npre-push = [\"test\"]"
+    )
+    .unwrap();
+
+    let err = run_cargo(&root, ["test"]).unwrap_err();
+    assert!(
+        err.contains("'package.metadata.husky'")
+            && err.contains("configuration is also defined in .cargo-husky.toml"),
+        "Unexpected output on `cargo test`: {}",
+        err
+    );
+}
+
+fn add_workspace_member(root: &Path, name: &str, config: &str) {
+    let dir = root.join("crates").join(name);
+    fs::create_dir_all(dir.join("src")).unwrap();
+    File::create(dir.join("src").join("lib.rs")).unwrap();
+    let mut cargo_toml = File::create(dir.join("Cargo.toml")).unwrap();
+    writeln!(
+        cargo_toml,
+        "[package]\nname = \"{}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n{}",
+        name, config
+    )
+    .unwrap();
+}
+
+#[test]
+fn workspace_config() {
+    let root = cargo_project_for("workspace-config");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "\n[workspace]\nmembers = [\"crates/*\"]\n\n[workspace.metadata.husky.hooks]\npre-commit = [\"fmt\"]\npre-push = [\"test\"]\n\n[workspace.metadata.husky.commands.test]\nargs = [\"--release\"]"
+    )
+    .unwrap();
+    add_workspace_member(
+        &root,
+        "member",
+        "[package.metadata.husky.hooks]\npre-commit = [\"check\"]",
+    );
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert!(
+        script.lines().any(|l| l == "cargo test --all --release"),
+        "{}",
+        script
+    );
+    // The member overrides the hook configured in the workspace
+    let script = get_hook_script(&root, "pre-commit").unwrap();
+    assert!(
+        script
+            .lines()
+            .any(|l| l == "cargo check --all-targets --all"),
+        "{}",
+        script
+    );
+    assert!(!script.contains("cargo fmt"), "{}", script);
+
+    let out = run_hook(&root, "pre-commit");
+    assert!(out.status.success(), "{:?}", out);
+    let stdout = str::from_utf8(&out.stdout).unwrap();
+    assert!(
+        stdout.contains("+cargo check --all-targets --all"),
+        "{}",
+        stdout
+    );
+    let out = run_hook(&root, "pre-push");
+    assert!(out.status.success(), "{:?}", out);
+    let stderr = str::from_utf8(&out.stderr).unwrap();
+    assert!(stderr.contains("`release` profile"), "{}", stderr);
+
+    let root = cargo_project_for("workspace-config-conflict");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(cargo_toml, "\n[workspace]\nmembers = [\"crates/*\"]").unwrap();
+    for member in &["member1", "member2"] {
+        add_workspace_member(
+            &root,
+            member,
+            "[package.metadata.husky.hooks]\npre-commit = [\"check\"]",
+        );
+    }
+    let err = run_cargo(&root, ["test"]).unwrap_err();
+    assert!(
+        err.contains("'package.metadata.husky.hooks.pre-commit'")
+            && err.contains("member2")
+            && err.contains("also configured in"),
+        "Unexpected output on `cargo test`: {}",
+        err
+    );
+}
+
+#[test]
+fn local_config_file() {
+    let root = cargo_project_for("local-config-file");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"prepush-hook\", \"run-cargo-test\", \"run-cargo-check\"]"
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let mut f = File::create(root.join(".cargo-husky.local.toml")).unwrap();
+    writeln!(
+        f,
+        "skip = [\"check\"]\n\n[pre-push]\nskip = [\"test\"]\nrun = [\"echo 'local command'\"]\n\n[pre-commit]\nrun = [\"false\"]"
+    )
+    .unwrap();
+    let output = run_hook(&root, "pre-push");
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    assert!(!stdout.contains("+cargo check"), "{}", stdout);
+    assert!(!stdout.contains("+cargo test"), "{}", stdout);
+    assert!(
+        stdout.contains("+echo 'local command'\nlocal command"),
+        "{}",
+        stdout
+    );
+
+    fs::remove_file(root.join(".cargo-husky.local.toml")).unwrap();
+    let output = run_hook(&root, "pre-push");
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    assert!(stdout.contains("+cargo check"), "{}", stdout);
+    assert!(stdout.contains("+cargo test"), "{}", stdout);
+}
+
+#[test]
+fn local_config_file_with_whitespace() {
+    let root = cargo_project_for("local-config-file-whitespace");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"prepush-hook\", \"run-cargo-test\", \"run-cargo-check\"]"
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    fs::write(
+        root.join(".cargo-husky.local.toml"),
+        "\tskip = [ \"check\" ] # comment\n\n  [ pre-push ]  # comment\n\tskip=[\"test\"]\n  run = [\"echo one\", \"echo two\"]\n",
+    )
+    .unwrap();
+    let output = run_hook(&root, "pre-push");
+    assert!(output.status.success(), "{:?}", output);
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    assert_eq!(stdout, "+echo one\none\n+echo two\ntwo\n");
+}
+
+#[test]
+fn env_var_substitution() {
+    let root = cargo_project_for("env-var-substitution");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "\n[package.metadata.husky.hooks]\npre-push = [\"test\"]\npre-commit = [\"greet\"]\n\n[package.metadata.husky.commands.test]\nargs = [\"-j\", \"${{HUSKY_JOBS:-4}}\"]\n\n[package.metadata.husky.commands.greet]\nrun = \"echo hello ${{HUSKY_NAME:-world}}\""
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert!(
+        script
+            .lines()
+            .any(|l| l == "cargo test --all -j \"${HUSKY_JOBS:-4}\""),
+        "{}",
+        script
+    );
+
+    let output = run_hook(&root, "pre-commit");
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    assert!(
+        stdout.contains("+echo hello world\nhello world"),
+        "{}",
+        stdout
+    );
+    let output = command("sh")
+        .arg(hook_path(&root, "pre-commit"))
+        .env("HUSKY_NAME", "husky")
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    assert!(
+        stdout.contains("+echo hello husky\nhello husky"),
+        "{}",
+        stdout
+    );
+
+    let root = cargo_project_for("invalid-env-var-substitution");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "\n[package.metadata.husky.commands.test]\nargs = [\"-j\", \"${{HUSKY JOBS}}\"]"
+    )
+    .unwrap();
+    let err = run_cargo(&root, ["test"]).unwrap_err();
+    assert!(
+        err.contains("'package.metadata.husky.commands.test.args'")
+            && err.contains("invalid substitution '${HUSKY JOBS}'"),
+        "Unexpected output on `cargo test`: {}",
+        err
+    );
+}
+
+#[test]
+fn command_order() {
+    let root = cargo_project_for("command-order");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "\n[package.metadata.husky.hooks]\npre-push = [\"test\", \"clippy\", \"codegen\"]\n\n[package.metadata.husky.commands.test]\nafter = [\"clippy\"]\n\n[package.metadata.husky.commands.clippy]\nafter = [\"codegen\"]\n\n[package.metadata.husky.commands.codegen]\nrun = \"echo codegen\""
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    let position = |cmd: &str| script.lines().position(|l| l == cmd).unwrap();
+    assert!(
+        position("echo codegen") < position("cargo clippy --all -- -D warnings"),
+        "{}",
+        script
+    );
+    assert!(
+        position("cargo clippy --all -- -D warnings") < position("cargo test --all"),
+        "{}",
+        script
+    );
+
+    let out = run_hook(&root, "pre-push");
+    assert!(out.status.success(), "{:?}", out);
+    let stdout = str::from_utf8(&out.stdout).unwrap();
+    let position = |cmd: &str| stdout.find(cmd).unwrap();
+    assert!(
+        position("+echo codegen") < position("+cargo clippy")
+            && position("+cargo clippy") < position("+cargo test"),
+        "{}",
+        stdout
+    );
+
+    let root = cargo_project_for("command-order-cycle");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "\n[package.metadata.husky.commands.test]\nafter = [\"clippy\"]\n\n[package.metadata.husky.commands.clippy]\nafter = [\"test\"]"
+    )
+    .unwrap();
+    let err = run_cargo(&root, ["test"]).unwrap_err();
+    assert!(
+        err.contains("'package.metadata.husky.commands.clippy.after'")
+            && err.contains("commands depend on each other: clippy -> test -> clippy"),
+        "Unexpected output on `cargo test`: {}",
+        err
+    );
+}
+
+#[test]
+fn env_vars_in_hooks() {
+    let root = cargo_project_for("env-vars-in-hooks");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "\n[package.metadata.husky.hooks]\npre-commit = [\"greet\"]\n\n[package.metadata.husky.commands.greet]\nrun = \"echo $GREETING $RUST_BACKTRACE\"\n\n[package.metadata.husky.env]\nRUST_BACKTRACE = \"1\"\nGREETING = \"hi\"\n\n[package.metadata.husky.env.pre-commit]\nGREETING = \"hello ${{NAME:-world}}\""
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert!(script.contains("\nexport GREETING=\"hi\"\n"), "{}", script);
+    assert!(
+        script.contains("\nexport RUST_BACKTRACE=\"1\"\n"),
+        "{}",
+        script
+    );
+
+    let output = run_hook(&root, "pre-commit");
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    assert!(stdout.contains("\nhello world 1\n"), "{}", stdout);
+
+    let root = cargo_project_for("invalid-env-var-name");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "\n[package.metadata.husky.env]\n\"RUST-LOG\" = \"debug\""
+    )
+    .unwrap();
+    let err = run_cargo(&root, ["test"]).unwrap_err();
+    assert!(
+        err.contains("'package.metadata.husky.env.RUST-LOG'")
+            && err.contains("invalid environment variable name 'RUST-LOG'"),
+        "Unexpected output on `cargo test`: {}",
+        err
+    );
+}
+
+#[test]
+fn path_filtered_commands() {
+    let root = cargo_project_for("path-filtered-commands");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "\n[package.metadata.husky.hooks]\npre-commit = [\"rust\"]\n\n[package.metadata.husky.commands.rust]\nrun = \"echo rust files changed\"\npaths = [\"src/**\", \"Cargo.*\"]"
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let git = |args: &[&str]| {
+        command("git")
+            .args(args)
+            .current_dir(&root)
+            .output()
+            .unwrap()
+    };
+    fs::write(root.join("README.md"), "# docs\n").unwrap();
+    git(&["add", "README.md"]);
+    let out = run_hook(&root, "pre-commit");
+    assert!(out.status.success(), "{:?}", out);
+    let stdout = str::from_utf8(&out.stdout).unwrap();
+    assert!(!stdout.contains("rust files changed"), "{}", stdout);
+
+    git(&["add", "src/lib.rs"]);
+    let out = run_hook(&root, "pre-commit");
+    assert!(out.status.success(), "{:?}", out);
+    let stdout = str::from_utf8(&out.stdout).unwrap();
+    assert!(stdout.contains("rust files changed"), "{}", stdout);
+}
+
+#[test]
+fn profiles_selected_at_runtime() {
+    let root = cargo_project_for("profiles-selected-at-runtime");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        r#"
+[package.metadata.husky.hooks]
+pre-commit = ["quick", "slow"]
+
+[package.metadata.husky.profiles.fast.hooks]
+pre-commit = ["quick"]
+
+[package.metadata.husky.profiles.full.hooks]
+pre-commit = ["quick", "slow", "extra"]
+
+[package.metadata.husky.commands.quick]
+run = "echo quick check"
+
+[package.metadata.husky.commands.slow]
+run = "echo slow check"
+
+[package.metadata.husky.commands.extra]
+run = "echo extra check""#
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let run_with_profile = |profile: &str| {
+        let output = command("sh")
+            .arg(hook_path(&root, "pre-commit"))
+            .env("HUSKY_PROFILE", profile)
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let checks = stdout
+            .lines()
+            .filter(|l| l.ends_with(" check") && !l.starts_with('+'))
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        (output.status.success(), checks, stdout)
+    };
+
+    let (ok, checks, _) = run_with_profile("");
+    assert!(ok);
+    assert_eq!(checks, ["quick check", "slow check"]);
+    let (ok, checks, _) = run_with_profile("fast");
+    assert!(ok);
+    assert_eq!(checks, ["quick check"]);
+    let (ok, checks, _) = run_with_profile("full");
+    assert!(ok);
+    assert_eq!(checks, ["quick check", "slow check", "extra check"]);
+    let (ok, checks, stdout) = run_with_profile("fsat");
+    assert!(!ok);
+    assert!(checks.is_empty(), "{:?}", checks);
+    assert!(
+        stdout.contains(
+            "Unknown profile 'fsat' in $HUSKY_PROFILE. Available profiles are: fast, full"
+        ),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn common_config_mistakes() {
+    let cases = [
+        (
+            "[package.metadata.husky.hook]\npre-push = [\"test\"]",
+            "'package.metadata.husky.hook'",
+            "unknown key 'hook'. Did you mean 'hooks'?",
+        ),
+        (
+            "[package.metadata.husky.hooks]\nprecommit = [\"test\"]",
+            "'package.metadata.husky.hooks.precommit'",
+            "unknown hook 'precommit'. Did you mean 'pre-commit'?",
+        ),
+        (
+            "[package.metadata.husky.hooks]\npre-push = [\"clipy\"]",
+            "'package.metadata.husky.hooks.pre-push'",
+            "unknown command 'clipy'. Did you mean 'clippy'?",
+        ),
+        (
+            "[package.metadata.husky.hooks]\npre-push = [\"test\", \"clippy\", \"test\"]",
+            "'package.metadata.husky.hooks.pre-push'",
+            "command 'test' is listed more than once",
+        ),
+        (
+            "[package.metadata.husky.commands.lint]\nrun = \" \"",
+            "'package.metadata.husky.commands.lint.run'",
+            "command must not be empty",
+        ),
+        (
+            "[package.metadata.husky.commands.test]\ntoolchain = \"+nightly\"",
+            "'package.metadata.husky.commands.test.toolchain'",
+            "invalid toolchain '+nightly'",
+        ),
+        (
+            "[package.metadata.husky.commands.test]\ntoolchain = \"nightly; rm -rf ~\"",
+            "'package.metadata.husky.commands.test.toolchain'",
+            "invalid toolchain 'nightly; rm -rf ~'",
+        ),
+        (
+            "[package.metadata.husky.commands.test]\npaths = [\"./src/*\"]",
+            "'package.metadata.husky.commands.test.paths'",
+            "path pattern './src/*' must be relative to the repository root",
+        ),
+        (
+            "[package.metadata.husky.commands.test]\npaths = [\"src/\"]",
+            "'package.metadata.husky.commands.test.paths'",
+            "Use 'src/*' to match files in the directory",
+        ),
+    ];
+
+    for (i, (config, key, message)) in cases.iter().enumerate() {
+        let root = cargo_project_for(&format!("common-config-mistakes-{}", i));
+        let mut cargo_toml = open_cargo_toml(&root);
+        writeln!(cargo_toml, "\n{}", config).unwrap();
+
+        let err = run_cargo(&root, ["test"]).unwrap_err();
+        assert!(
+            err.contains(key) && err.contains(message),
+            "Unexpected output on `cargo test` with {:?}: {}",
+            config,
+            err
+        );
+        // No broken hook is generated
+        assert_eq!(get_hook_script(&root, "pre-push"), None);
+    }
+}
+
+#[test]
+#[cfg(not(target_os = "windows"))]
+fn hook_dispatcher() {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let root = cargo_project_for("hook-dispatcher");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"hook-dispatcher\"]\n\n[package.metadata.husky.hooks]\npre-commit = [\"hello\"]\n\n[package.metadata.husky.commands.hello]\nrun = \"echo hello from cargo-husky\""
+    )
+    .unwrap();
+
+    // Hook script put by other tool
+    let mut f = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o755)
+        .open(hook_path(&root, "pre-commit"))
+        .unwrap();
+    writeln!(f, "#!/bin/sh\necho hello from other tool").unwrap();
+    drop(f);
+
+    run_cargo(&root, ["test"]).unwrap();
+
+    let dir = hook_path(&root, "pre-commit.d");
+    let moved = fs::read_to_string(dir.join("pre-commit")).unwrap();
+    assert!(moved.contains("echo hello from other tool"), "{}", moved);
+    let script = fs::read_to_string(dir.join("cargo-husky")).unwrap();
+    assert!(
+        script.lines().any(|l| l == "echo hello from cargo-husky"),
+        "{}",
+        script
+    );
+
+    let output = run_hook(&root, "pre-commit");
+    assert!(output.status.success(), "{:?}", output);
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    assert!(
+        stdout.contains("\nhello from cargo-husky\n")
+            && stdout.ends_with("hello from other tool\n"),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+#[cfg(not(target_os = "windows"))]
+fn hook_dispatcher_passes_stdin() {
+    use std::os::unix::fs::OpenOptionsExt;
+    use std::process::Stdio;
+
+    let root = cargo_project_for("hook-dispatcher-stdin");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"hook-dispatcher\"]\n\n[package.metadata.husky.hooks]\npre-push = [\"hello\"]\n\n[package.metadata.husky.commands.hello]\nrun = \"echo hello from cargo-husky\""
+    )
+    .unwrap();
+
+    let mut f = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o755)
+        .open(hook_path(&root, "pre-push"))
+        .unwrap();
+    writeln!(f, "#!/bin/sh\necho \"stdin: $(wc -c | tr -d ' ')\"").unwrap();
+    drop(f);
+
+    run_cargo(&root, ["test"]).unwrap();
+
+    for input in &["", "refs/heads/main 0123 refs/heads/main 4567\n"] {
+        let mut child = command("sh")
+            .arg(hook_path(&root, "pre-push"))
+            .current_dir(&root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(input.as_bytes())
+            .unwrap();
+        let output = child.wait_with_output().unwrap();
+        assert!(output.status.success(), "{:?}", output);
+        let stdout = str::from_utf8(&output.stdout).unwrap();
+        let expected = format!("stdin: {}\n", input.len());
+        assert!(stdout.ends_with(&expected), "{}", stdout);
+    }
+}
+
+#[test]
+#[cfg(not(target_os = "windows"))]
+fn chain_existing_hooks() {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let root = cargo_project_for("chain-existing-hooks");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"chain-existing-hooks\"]\n\n[package.metadata.husky.hooks]\npre-commit = [\"hello\"]\n\n[package.metadata.husky.commands.hello]\nrun = \"echo hello from cargo-husky\""
+    )
+    .unwrap();
+
+    let mut f = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o755)
+        .open(hook_path(&root, "pre-commit"))
+        .unwrap();
+    writeln!(f, "#!/bin/sh\necho hello from other tool").unwrap();
+    drop(f);
+
+    run_cargo(&root, ["test"]).unwrap();
+
+    let local = get_hook_script(&root, "pre-commit.local").unwrap();
+    assert!(local.contains("echo hello from other tool"), "{}", local);
+
+    let output = run_hook(&root, "pre-commit");
+    assert!(output.status.success(), "{:?}", output);
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    let other = stdout.find("\nhello from other tool\n").unwrap();
+    let husky = stdout.find("\nhello from cargo-husky\n").unwrap();
+    assert!(other < husky, "{}", stdout);
+}
+
+#[test]
+fn embed_in_existing_hooks() {
+    let root = cargo_project_for("embed-in-existing-hooks");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"embed-in-existing-hooks\"]\n\n[package.metadata.husky.hooks]\npre-commit = [\"hello\"]\npost-merge = [\"hello\"]\n\n[package.metadata.husky.commands.hello]\nrun = \"echo hello from cargo-husky\""
+    )
+    .unwrap();
+    fs::write(
+        hook_path(&root, "pre-commit"),
+        "#!/bin/sh\necho hello from user\n",
+    )
+    .unwrap();
+    let python = "#!/usr/bin/env python3\nprint('hello')\n";
+    fs::write(hook_path(&root, "post-merge"), python).unwrap();
+
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-commit").unwrap();
+    assert!(
+        script.starts_with("#!/bin/sh\necho hello from user\n\n# BEGIN cargo-husky\n"),
+        "{}",
+        script
+    );
+    assert!(
+        script.ends_with(") || exit $?\n# END cargo-husky\n"),
+        "{}",
+        script
+    );
+    let output = run_hook(&root, "pre-commit");
+    assert!(output.status.success(), "{:?}", output);
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    assert!(
+        stdout.starts_with("hello from user\n") && stdout.ends_with("\nhello from cargo-husky\n"),
+        "{}",
+        stdout
+    );
+    assert_eq!(get_hook_script(&root, "post-merge").unwrap(), python);
+
+    // Only the block is updated when cargo-husky is updated
+    let current = format!("set by cargo-husky v{}", env!("CARGO_PKG_VERSION"));
+    let prev_version = decrease_patch(SemVer::parse(env!("CARGO_PKG_VERSION")).unwrap());
+    let prev = format!("set by cargo-husky v{}", prev_version);
+    let edited = script.replacen(&current, &prev, 1).replacen(
+        "echo hello from user",
+        "echo edited by user",
+        1,
+    );
+    fs::write(hook_path(&root, "pre-commit"), &edited).unwrap();
+    fs::remove_dir_all(root.join("target")).unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let updated = get_hook_script(&root, "pre-commit").unwrap();
+    assert_eq!(
+        updated,
+        script.replacen("echo hello from user", "echo edited by user", 1)
+    );
+}
+
+#[test]
+fn tracked_hooks_dir() {
+    let root = cargo_project_for("tracked-hooks-dir");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(cargo_toml, "features = [\"tracked-hooks-dir\"]").unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = fs::read_to_string(root.join(".githooks").join("pre-push")).unwrap();
+    assert!(
+        script.lines().any(|l| l == "cargo test --all"),
+        "{}",
+        script
+    );
+    assert_eq!(get_hook_script(&root, "pre-push"), None);
+
+    let output = command("git")
+        .args(["config", "core.hooksPath"])
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    assert_eq!(str::from_utf8(&output.stdout).unwrap(), ".githooks\n");
+
+    // Committed hooks do not contain paths on the machine
+    let cargo = PathBuf::from(env::var("CARGO").unwrap());
+    for path in &[
+        root.as_path(),
+        Path::new(env!("CARGO_MANIFEST_DIR")),
+        &cargo,
+    ] {
+        let path = path.to_string_lossy();
+        assert!(!script.contains(path.as_ref()), "{}: {}", path, script);
+    }
+    let output = command("sh")
+        .arg(root.join(".githooks").join("pre-push"))
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+
+    // Regenerated hooks are not backed up in the tracked directory
+    writeln!(
+        cargo_toml,
+        "\n[package.metadata.husky.hooks]\npre-push = [\"check\"]"
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+    let script = fs::read_to_string(root.join(".githooks").join("pre-push")).unwrap();
+    assert!(script.contains("cargo check"), "{}", script);
+    let entries = fs::read_dir(root.join(".githooks"))
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(entries, vec!["pre-push".to_string()]);
+
+    // Uninstalling unsets `core.hooksPath` set by cargo-husky
+    let output = command("cargo")
+        .arg("test")
+        .env("CARGO_HUSKY_UNINSTALL", "1")
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    assert!(!root.join(".githooks").join("pre-push").exists());
+    let output = command("git")
+        .args(["config", "core.hooksPath"])
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    assert!(!output.status.success(), "{:?}", output);
+}
+
+#[test]
+fn script_header_and_footer() {
+    let root = cargo_project_for("script-header-and-footer");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "\n[package.metadata.husky]\nheader = \"PRELUDE=loaded\"\nfooter = \"echo '{{{{hook_name}}}} passed'\"\n\n[package.metadata.husky.hooks]\npre-commit = [\"greet\"]\n\n[package.metadata.husky.commands.greet]\nrun = \"echo prelude $PRELUDE\""
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-commit").unwrap();
+    assert!(
+        script
+            .lines()
+            .nth(2)
+            .unwrap()
+            .starts_with("# This hook was set by cargo-husky v"),
+        "{}",
+        script
+    );
+    assert!(
+        script.contains("#\n\nPRELUDE=loaded\nset -e\n"),
+        "{}",
+        script
+    );
+    assert!(
+        script.ends_with("\necho 'pre-commit passed'\n"),
+        "{}",
+        script
+    );
+
+    let output = run_hook(&root, "pre-commit");
+    assert!(output.status.success(), "{:?}", output);
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    assert!(
+        stdout.contains("prelude loaded\npre-commit passed\n"),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn configurable_shell() {
+    let root = cargo_project_for("configurable-shell");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "\n[package.metadata.husky]\nshell = \"/usr/bin/env bash\"\n\n[package.metadata.husky.hooks]\npre-commit = [\"greet\"]\n\n[package.metadata.husky.commands.greet]\nrun = \"test -n \\\"$BASH_VERSION\\\"\""
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-commit").unwrap();
+    assert!(script.starts_with("#!/usr/bin/env bash\n#\n"), "{}", script);
+    let output = command(hook_path(&root, "pre-commit"))
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+
+    let root = cargo_project_for("configurable-shell-per-hook");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "\n[package.metadata.husky.shell]\npre-push = \"/bin/bash -e\""
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert!(script.starts_with("#!/bin/bash -e\n"), "{}", script);
+
+    let root = cargo_project_for("invalid-shell");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(cargo_toml, "\n[package.metadata.husky]\nshell = \"bash\"").unwrap();
+    let err = run_cargo(&root, ["test"]).unwrap_err();
+    assert!(
+        err.contains("'package.metadata.husky.shell'")
+            && err.contains("interpreter must be an absolute path but got 'bash'"),
+        "Unexpected output on `cargo test`: {}",
+        err
+    );
+}
+
+#[test]
+fn lf_line_endings_with_crlf_config() {
+    let root = cargo_project_for("lf-line-endings-with-crlf-config");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "\n[package.metadata.husky]\nfooter = \"\"\"\necho footer\necho done\n\"\"\"\n\n[package.metadata.husky.hooks]\npre-commit = [\"greet\"]\n\n[package.metadata.husky.commands.greet]\nrun = \"\"\"\necho one\necho two\"\"\""
+    )
+    .unwrap();
+    drop(cargo_toml);
+    let manifest = fs::read_to_string(root.join("Cargo.toml")).unwrap();
+    fs::write(root.join("Cargo.toml"), manifest.replace('\n', "\r\n")).unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-commit").unwrap();
+    assert!(!script.contains('\r'), "{:?}", script);
+    let output = run_hook(&root, "pre-commit");
+    assert!(output.status.success(), "{:?}", output);
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    assert!(stdout.contains("one\ntwo\n"), "{}", stdout);
+    assert!(stdout.ends_with("footer\ndone\n"), "{}", stdout);
+}
+
+#[test]
+#[cfg(not(target_os = "windows"))]
+fn cargo_found_with_minimal_path() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let root = cargo_project_for("cargo-found-with-minimal-path");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "\n[package.metadata.husky.hooks]\npre-commit = [\"version\"]\n\n[package.metadata.husky.commands.version]\nrun = \"command -v cargo\""
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    // $HOME without .cargo falls back to the directory of cargo which installed the hook. Under
+    // rustup, it is the directory of rustup proxies so that `cargo +<toolchain>` works
+    let home = root.join("home");
+    fs::create_dir_all(&home).unwrap();
+    let output = command("sh")
+        .arg(hook_path(&root, "pre-commit"))
+        .env("PATH", "/usr/bin:/bin")
+        .env("HOME", &home)
+        .env_remove("CARGO_HOME")
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    let real_home = PathBuf::from(env::var_os("HOME").unwrap());
+    let toolchains = env::var_os("RUSTUP_HOME")
+        .map_or_else(|| real_home.join(".rustup"), PathBuf::from)
+        .join("toolchains");
+    let mut cargo = PathBuf::from(env::var("CARGO").unwrap());
+    if cargo.starts_with(&toolchains) {
+        cargo = env::var_os("CARGO_HOME")
+            .map_or_else(|| real_home.join(".cargo"), PathBuf::from)
+            .join("bin")
+            .join("cargo");
+    }
+    assert!(
+        stdout.ends_with(&format!("{}\n", cargo.display())),
+        "{}",
+        stdout
+    );
+
+    // `$HOME/.cargo/env` is sourced when it exists
+    let bin_dir = home.join(".cargo").join("bin");
+    fs::create_dir_all(&bin_dir).unwrap();
+    fs::write(bin_dir.join("cargo"), "#!/bin/sh\n").unwrap();
+    fs::set_permissions(bin_dir.join("cargo"), fs::Permissions::from_mode(0o755)).unwrap();
+    fs::write(
+        home.join(".cargo").join("env"),
+        "PATH=\"$HOME/.cargo/bin:$PATH\"\n",
+    )
+    .unwrap();
+    let output = command("sh")
+        .arg(hook_path(&root, "pre-commit"))
+        .env("PATH", "/usr/bin:/bin")
+        .env("HOME", &home)
+        .env_remove("CARGO_HOME")
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    assert!(
+        stdout.ends_with(&format!("{}\n", bin_dir.join("cargo").display())),
+        "{}",
+        stdout
+    );
+
+    // `$CARGO_HOME/bin` is searched even when it has no env script
+    let cargo_home = root.join("cargo-home");
+    let bin_dir = cargo_home.join("bin");
+    fs::create_dir_all(&bin_dir).unwrap();
+    fs::write(bin_dir.join("cargo"), "#!/bin/sh\n").unwrap();
+    fs::set_permissions(bin_dir.join("cargo"), fs::Permissions::from_mode(0o755)).unwrap();
+    let output = command("sh")
+        .arg(hook_path(&root, "pre-commit"))
+        .env("PATH", "/usr/bin:/bin")
+        .env("HOME", root.join("empty-home"))
+        .env("CARGO_HOME", &cargo_home)
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    assert!(
+        stdout.ends_with(&format!("{}\n", bin_dir.join("cargo").display())),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn custom_command_dialects() {
+    let root = cargo_project_for("custom-command-dialects");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "\n[package.metadata.husky.hooks]\npre-push = [\"ps\", \"fish\", \"nu\"]\n\n[package.metadata.husky.commands.ps]\nrun = \"Write-Output \\\"$env:HOME\\\"\"\ndialect = \"powershell\"\n\n[package.metadata.husky.commands.fish]\nrun = \"echo (pwd) ${{HUSKY_NAME:-world}}\"\ndialect = \"fish\"\n\n[package.metadata.husky.commands.nu]\nrun = \"ls | length\"\ndialect = \"nushell\""
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    for line in [
+        r#"pwsh -NoProfile -NonInteractive -Command "Write-Output \"\$env:HOME\"""#,
+        r#"fish --no-config -c "echo (pwd) ${HUSKY_NAME:-world}""#,
+        r#"nu --no-config-file -c "ls | length""#,
+    ] {
+        assert!(script.lines().any(|l| l == line), "{}", script);
+    }
+
+    let out = run_hook_with_fake_tools(&root, "pre-push", &["pwsh", "fish", "nu"]);
+    assert!(out.status.success(), "{:?}", out);
+    let stdout = str::from_utf8(&out.stdout).unwrap();
+    for line in [
+        r#"pwsh -NoProfile -NonInteractive -Command Write-Output "$env:HOME""#,
+        "fish --no-config -c echo (pwd) world",
+        "nu --no-config-file -c ls | length",
+    ] {
+        assert!(stdout.lines().any(|l| l == line), "{}", stdout);
+    }
+
+    let root = cargo_project_for("unknown-custom-command-dialect");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "\n[package.metadata.husky.commands.greet]\nrun = \"echo hello\"\ndialect = \"bash\""
+    )
+    .unwrap();
+    let err = run_cargo(&root, ["test"]).unwrap_err();
+    assert!(
+        err.contains("'package.metadata.husky.commands.greet.dialect'")
+            && err.contains("unknown value 'bash'"),
+        "Unexpected output on `cargo test`: {}",
+        err
+    );
+}
+
+#[test]
+#[cfg(not(target_os = "windows"))]
+fn regenerated_hook_is_executable() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let root = cargo_project_for("regenerated-hook-is-executable");
+    run_cargo(&root, ["test"]).unwrap();
+
+    // The hook generated by the previous version lost its executable bit
+    let prepush_path = hook_path(&root, "pre-push");
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    let before = format!("set by cargo-husky v{}", env!("CARGO_PKG_VERSION"));
+    let prev_version = decrease_patch(SemVer::parse(env!("CARGO_PKG_VERSION")).unwrap());
+    let after = format!("set by cargo-husky v{}", prev_version);
+    fs::write(&prepush_path, script.replacen(&before, &after, 1)).unwrap();
+    fs::set_permissions(&prepush_path, fs::Permissions::from_mode(0o644)).unwrap();
+    fs::remove_dir_all(root.join("target")).unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let mode = fs::metadata(&prepush_path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o555, 0o555);
+    assert_eq!(get_hook_script(&root, "pre-push").unwrap(), script);
+}
+
+#[test]
+fn special_characters_in_paths() {
+    let root = cargo_project_for("special-characters-in-paths");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"prepush-hook\", \"precommit-hook\", \"run-cargo-bench\"]\n\n[package.metadata.husky.hooks]\npre-commit = [\"where\"]\n\n[package.metadata.husky.commands.where]\nrun = \"pwd\"\n\n[package.metadata.husky.commands.bench]\nbaseline = \"my 'base' ü\""
+    )
+    .unwrap();
+    drop(cargo_toml);
+    let special = TMPDIR_ROOT.join("with space 'quote' \"double\" ü");
+    ensure_empty_dir(&special);
+    fs::remove_dir(&special).unwrap();
+    fs::rename(&root, &special).unwrap();
+    let root = special;
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert!(
+        script
+            .lines()
+            .any(|l| l == r#"    cargo bench -- --save-baseline 'my '\''base'\'' ü'"#),
+        "{}",
+        script
+    );
+    let output = command("sh")
+        .arg("-n")
+        .arg(hook_path(&root, "pre-push"))
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let message = script
+        .lines()
+        .find(|l| l.contains("Benchmarks regressed"))
+        .unwrap();
+    let output = command("sh").args(["-c", message]).output().unwrap();
+    assert_eq!(
+        str::from_utf8(&output.stdout).unwrap(),
+        "Benchmarks regressed more than 5% compared to baseline 'my 'base' ü':\n"
+    );
+
+    let output = run_hook(&root, "pre-commit");
+    assert!(output.status.success(), "{:?}", output);
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    assert!(
+        stdout.ends_with(&format!("{}\n", root.display())),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+#[cfg(not(target_os = "windows"))]
+fn symlinked_target_dir() {
+    use std::os::unix::fs::symlink;
+
+    let root = cargo_project_for("symlinked-target-dir");
+    fs::create_dir_all(root.join("target")).unwrap();
+    let link = TMPDIR_ROOT.join("symlinked-target-dir-link");
+    symlink(root.join("target"), &link).unwrap();
+
+    let output = command("cargo")
+        .arg("test")
+        .env("CARGO_TARGET_DIR", &link)
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let stderr = str::from_utf8(&output.stderr).unwrap();
+    assert!(
+        !stderr.contains(".git directory was not found"),
+        "{}",
+        stderr
+    );
+
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert!(
+        script.lines().any(|l| l == "cargo test --all"),
+        "{}",
+        script
+    );
+}
+
+#[test]
+fn linked_worktree() {
+    let root = cargo_project_for("linked-worktree");
+    let worktree = TMPDIR_ROOT.join("linked-worktree-linked");
+    for args in [
+        vec!["add", "-A"],
+        vec![
+            "-c",
+            "user.name=husky",
+            "-c",
+            "user.email=husky@example.com",
+            "commit",
+            "-q",
+            "-m",
+            "init",
+        ],
+        vec!["worktree", "add", "-q", worktree.to_str().unwrap()],
+    ] {
+        let output = command("git")
+            .args(&args)
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+    }
+    run_cargo(&worktree, ["test"]).unwrap();
+
+    // Hooks are shared by all worktrees
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert!(
+        script.lines().any(|l| l == "cargo test --all"),
+        "{}",
+        script
+    );
+    let gitdir = root
+        .join(".git")
+        .join("worktrees")
+        .join("linked-worktree-linked");
+    assert!(!gitdir.join("hooks").exists());
+}
+
+#[test]
+fn configured_hooks_path() {
+    let root = cargo_project_for("configured-hooks-path");
+    let output = command("git")
+        .args(["config", "core.hooksPath", "custom/hooks"])
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = fs::read_to_string(root.join("custom").join("hooks").join("pre-push")).unwrap();
+    assert!(
+        script.lines().any(|l| l == "cargo test --all"),
+        "{}",
+        script
+    );
+    assert_eq!(get_hook_script(&root, "pre-push"), None);
+}
+
+#[test]
+fn global_hooks_path_outside_repository() {
+    let root = cargo_project_for("global-hooks-path");
+    let global_hooks = tmpdir_for("global-hooks-path-hooks");
+    let gitconfig = tmpdir_for("global-hooks-path-config").join("gitconfig");
+    fs::write(
+        &gitconfig,
+        format!("[core]\n\thooksPath = {}\n", global_hooks.display()),
+    )
+    .unwrap();
+    let output = command("cargo")
+        .arg("test")
+        .env("GIT_CONFIG_GLOBAL", &gitconfig)
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let stderr = str::from_utf8(&output.stderr).unwrap();
+    assert!(
+        stderr.contains("is outside the repository")
+            && stderr.contains("Skipped installing Git hooks"),
+        "{}",
+        stderr
+    );
+    assert_eq!(fs::read_dir(&global_hooks).unwrap().count(), 0);
+    assert_eq!(get_hook_script(&root, "pre-push"), None);
+
+    // Relative path may point outside the repository as well
+    let root = cargo_project_for("relative-hooks-path-outside");
+    let output = command("git")
+        .args(["config", "core.hooksPath", "custom/../../outside-hooks"])
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let output = command("cargo")
+        .arg("test")
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    assert!(!root.with_file_name("outside-hooks").exists());
+}
+
+#[test]
+fn git_dir_env_vars() {
+    let other = tmpdir_for("git-dir-env-vars-other");
+    let output = command("git")
+        .args(["init", "-q"])
+        .current_dir(&other)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+
+    for var in ["GIT_DIR", "GIT_COMMON_DIR"] {
+        let root = cargo_project_for(&format!("git-dir-env-vars-{}", var));
+        let output = command("cargo")
+            .arg("test")
+            .env(var, other.join(".git"))
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+
+        assert_eq!(get_hook_script(&root, "pre-push"), None);
+        let script = get_hook_script(&other, "pre-push").unwrap();
+        assert!(
+            script.lines().any(|l| l == "cargo test --all"),
+            "{}",
+            script
+        );
+        fs::remove_file(hook_path(&other, "pre-push")).unwrap();
+    }
+}
+
+#[test]
+fn relative_gitdir_in_git_file() {
+    // Same layout as submodules whose Git directory is in `.git/modules` of the superproject
+    let root = cargo_project_for("relative-gitdir-in-git-file");
+    let modules = tmpdir_for("relative-gitdir-in-git-file-modules");
+    fs::rename(root.join(".git"), modules.join("foo")).unwrap();
+    fs::write(
+        root.join(".git"),
+        "gitdir: ../relative-gitdir-in-git-file-modules/foo\n",
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = fs::read_to_string(modules.join("foo").join("hooks").join("pre-push")).unwrap();
+    assert!(
+        script.lines().any(|l| l == "cargo test --all"),
+        "{}",
+        script
+    );
+}
+
+#[test]
+fn target_dir_outside_repository() {
+    let root = cargo_project_for("target-dir-outside-repository");
+    let target = tmpdir_for("target-dir-outside-repository-target");
+    let output = command("cargo")
+        .arg("test")
+        .env("CARGO_TARGET_DIR", &target)
+        .env("PWD", &root)
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert!(
+        script.lines().any(|l| l == "cargo test --all"),
+        "{}",
+        script
+    );
+}
+
+#[test]
+fn no_working_tree() {
+    // Crate extracted from a source tarball
+    let root = cargo_project_for("no-working-tree");
+    fs::remove_dir_all(root.join(".git")).unwrap();
+    let output = command("cargo")
+        .arg("test")
+        .env("PWD", &root)
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let stderr = str::from_utf8(&output.stderr).unwrap();
+    assert!(
+        stderr.contains(".git directory was not found")
+            && stderr.contains("Skipped installing Git hooks"),
+        "{}",
+        stderr
+    );
+
+    // Crate put in a bare repository
+    let bare = tmpdir_for("no-working-tree-bare.git");
+    let output = command("git")
+        .args(["init", "-q", "--bare"])
+        .current_dir(&bare)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let root = bare.join("crate");
+    fs::rename(TMPDIR_ROOT.join("no-working-tree"), &root).unwrap();
+    fs::remove_dir_all(root.join("target")).unwrap();
+    let output = command("cargo")
+        .arg("test")
+        .env("PWD", &root)
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let stderr = str::from_utf8(&output.stderr).unwrap();
+    assert!(
+        stderr.contains("is bare and has no working tree")
+            && stderr.contains("Skipped installing Git hooks"),
+        "{}",
+        stderr
+    );
+    assert!(!bare.join("hooks").join("pre-push").exists());
+}
+
+#[test]
+fn nested_repositories() {
+    let outer = cargo_project_for("nested-repositories");
+    let inner = outer.join("inner");
+    fs::rename(cargo_project_for("nested-repositories-inner"), &inner).unwrap();
+
+    // The crate in the nested repository is built in the target directory of the outer one
+    let output = command("cargo")
+        .arg("test")
+        .env("CARGO_TARGET_DIR", outer.join("target"))
+        .env("PWD", &inner)
+        .current_dir(&inner)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    assert!(get_hook_script(&inner, "pre-push").is_some());
+    assert_eq!(get_hook_script(&outer, "pre-push"), None);
+    fs::remove_file(hook_path(&inner, "pre-push")).unwrap();
+
+    // The repository is selected by configuration
+    let mut cargo_toml = open_cargo_toml(&outer);
+    writeln!(
+        cargo_toml,
+        "\n[package.metadata.husky]\nrepository = \"inner\""
+    )
+    .unwrap();
+    fs::remove_dir_all(outer.join("target")).unwrap();
+    run_cargo(&outer, ["test"]).unwrap();
+    assert!(get_hook_script(&inner, "pre-push").is_some());
+    assert_eq!(get_hook_script(&outer, "pre-push"), None);
+
+    let root = cargo_project_for("nested-repositories-not-found");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "\n[package.metadata.husky]\nrepository = \"inner\""
+    )
+    .unwrap();
+    let err = run_cargo(&root, ["test"]).unwrap_err();
+    assert!(
+        err.contains("Repository configured by 'repository' was not found"),
+        "Unexpected output on `cargo test`: {}",
+        err
+    );
+}
+
+#[test]
+fn missing_hooks_dir() {
+    let root = cargo_project_for("missing-hooks-dir");
+    fs::remove_dir_all(root.join(".git").join("hooks")).unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert!(
+        script.lines().any(|l| l == "cargo test --all"),
+        "{}",
+        script
+    );
+    #[cfg(not(target_os = "windows"))]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = fs::metadata(root.join(".git").join("hooks"))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o700, 0o700);
+    }
+}
+
+#[test]
+#[cfg(not(target_os = "windows"))]
+fn readonly_hooks_dir() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let root = cargo_project_for("readonly-hooks-dir");
+    let hooks_dir = root.join(".git").join("hooks");
+    fs::set_permissions(&hooks_dir, fs::Permissions::from_mode(0o555)).unwrap();
+    // Permissions are not checked for privileged users such as root
+    let writable = fs::write(hooks_dir.join("probe"), "").is_ok();
+    if !writable {
+        let output = run_cargo(&root, ["test"]).unwrap();
+        let stderr = str::from_utf8(&output.stderr).unwrap();
+        assert!(
+            stderr.contains("Skipped installing Git hooks"),
+            "{}",
+            stderr
+        );
+        assert_eq!(get_hook_script(&root, "pre-push"), None);
+    }
+    fs::set_permissions(&hooks_dir, fs::Permissions::from_mode(0o755)).unwrap();
+    if writable {
+        return;
+    }
+
+    let root = cargo_project_for("readonly-hooks-dir-fatal");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(cargo_toml, "features = [\"fail-on-readonly-hooks\"]").unwrap();
+    let hooks_dir = root.join(".git").join("hooks");
+    fs::set_permissions(&hooks_dir, fs::Permissions::from_mode(0o555)).unwrap();
+    let err = run_cargo(&root, ["test"]).unwrap_err();
+    fs::set_permissions(&hooks_dir, fs::Permissions::from_mode(0o755)).unwrap();
+    assert!(err.contains("IO error"), "{}", err);
+}
+
+#[test]
+fn separate_git_dir() {
+    let root = cargo_project_for("separate-git-dir");
+    let git_dir = tmpdir_for("separate-git-dir-external");
+    fs::remove_dir_all(root.join(".git")).unwrap();
+    let worktree = TMPDIR_ROOT.join("separate-git-dir-worktree");
+    for args in [
+        vec![
+            "init",
+            "-q",
+            "--separate-git-dir",
+            git_dir.to_str().unwrap(),
+        ],
+        vec!["add", "-A"],
+        vec![
+            "-c",
+            "user.name=husky",
+            "-c",
+            "user.email=husky@example.com",
+            "commit",
+            "-q",
+            "-m",
+            "init",
+        ],
+        vec!["worktree", "add", "-q", worktree.to_str().unwrap()],
+    ] {
+        let output = command("git")
+            .args(&args)
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+    }
+    assert!(root.join(".git").is_file());
+
+    run_cargo(&root, ["test"]).unwrap();
+    let pre_push = git_dir.join("hooks").join("pre-push");
+    let script = fs::read_to_string(&pre_push).unwrap();
+    assert!(
+        script.lines().any(|l| l == "cargo test --all"),
+        "{}",
+        script
+    );
+
+    // Linked worktree of the repository shares the hooks in the external Git directory
+    fs::remove_file(&pre_push).unwrap();
+    run_cargo(&worktree, ["test"]).unwrap();
+    assert!(pre_push.exists());
+}
+
+#[test]
+fn uninstall_hooks() {
+    let root = cargo_project_for("uninstall-hooks");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"prepush-hook\", \"embed-in-existing-hooks\"]\n\n[package.metadata.husky.hooks]\npre-commit = [\"hello\"]\n\n[package.metadata.husky.commands.hello]\nrun = \"echo hello from cargo-husky\""
+    )
+    .unwrap();
+    let user_hook = "#!/bin/sh\necho hello from user\n";
+    fs::write(hook_path(&root, "pre-commit"), user_hook).unwrap();
+    let other_hook = "#!/bin/sh\necho hello from other tool\n";
+    fs::write(hook_path(&root, "post-merge"), other_hook).unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+    assert!(get_hook_script(&root, "pre-push").is_some());
+    assert_ne!(get_hook_script(&root, "pre-commit").unwrap(), user_hook);
+
+    let output = command("cargo")
+        .arg("test")
+        .env("CARGO_HUSKY_UNINSTALL", "1")
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+
+    // Only hooks set by cargo-husky are removed
+    assert_eq!(get_hook_script(&root, "pre-push"), None);
+    assert_eq!(get_hook_script(&root, "pre-commit").unwrap(), user_hook);
+    assert_eq!(get_hook_script(&root, "post-merge").unwrap(), other_hook);
+
+    // Hooks are installed again once the variable is unset
+    run_cargo(&root, ["test"]).unwrap();
+    assert!(get_hook_script(&root, "pre-push").is_some());
+}
+
+#[test]
+fn uninstall_restores_moved_hooks() {
+    for (name, feature) in &[
+        ("uninstall-chained-hooks", "chain-existing-hooks"),
+        ("uninstall-dispatched-hooks", "hook-dispatcher"),
+    ] {
+        let root = cargo_project_for(name);
+        let mut cargo_toml = open_cargo_toml(&root);
+        writeln!(
+            cargo_toml,
+            "default-features = false\nfeatures = [\"prepush-hook\", \"{}\"]",
+            feature
+        )
+        .unwrap();
+        let other_hook = "#!/bin/sh\necho hello from other tool\n";
+        fs::write(hook_path(&root, "pre-push"), other_hook).unwrap();
+        run_cargo(&root, ["test"]).unwrap();
+        assert_ne!(get_hook_script(&root, "pre-push").unwrap(), other_hook);
+
+        let output = command("cargo")
+            .arg("test")
+            .env("CARGO_HUSKY_UNINSTALL", "1")
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+
+        assert_eq!(get_hook_script(&root, "pre-push").unwrap(), other_hook);
+        let hooks_dir = root.join(".git").join("hooks");
+        assert!(!hooks_dir.join("pre-push.local").exists(), "{}", feature);
+        assert!(!hooks_dir.join("pre-push.d").exists(), "{}", feature);
+    }
+}
+
+#[test]
+fn installed_hooks_manifest() {
+    let root = cargo_project_for("installed-hooks-manifest");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"precommit-hook\", \"prepush-hook\", \"run-cargo-check\"]"
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+
+    let manifest = fs::read_to_string(root.join(".git").join("cargo-husky.json")).unwrap();
+    let hooks_dir = root.join(".git").join("hooks");
+    let expected = format!(
+        "{{\n  \"version\": \"{}\",\n  \"features\": [\"precommit-hook\", \"prepush-hook\", \"run-cargo-check\"],\n  \"hooksDir\": \"{}\",\n  \"hooks\": [\"pre-commit\", \"pre-push\"]\n}}\n",
+        env!("CARGO_PKG_VERSION"),
+        hooks_dir.to_string_lossy(),
+    );
+    assert_eq!(manifest, expected);
+
+    let output = command("cargo")
+        .arg("test")
+        .env("CARGO_HUSKY_UNINSTALL", "1")
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    assert!(!root.join(".git").join("cargo-husky.json").exists());
+
+    // Hooks left to other tools are not recorded
+    let root = cargo_project_for("installed-hooks-manifest-foreign");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(cargo_toml, "features = [\"precommit-hook\"]").unwrap();
+    let foreign = "#!/bin/sh\necho hello from other tool\n";
+    fs::write(hook_path(&root, "pre-push"), foreign).unwrap();
+    let output = run_cargo(&root, ["test", "-vv"]).unwrap();
+    let stderr = str::from_utf8(&output.stderr).unwrap();
+    assert!(
+        stderr.contains("was put by other tool. cargo-husky did not install pre-push hook"),
+        "{}",
+        stderr
+    );
+    assert_eq!(get_hook_script(&root, "pre-push").unwrap(), foreign);
+    let manifest = fs::read_to_string(root.join(".git").join("cargo-husky.json")).unwrap();
+    assert!(
+        manifest.contains("\"hooks\": [\"pre-commit\"]\n"),
+        "{}",
+        manifest
+    );
+}
+
+#[test]
+fn regenerate_hook_script_on_feature_change() {
+    let root = cargo_project_for("feature-change");
+    let cargo_toml = fs::read_to_string(root.join("Cargo.toml")).unwrap();
+    let features = "default-features = false\nfeatures = [\"prepush-hook\", \"run-cargo-test\"]\n";
+    fs::write(
+        root.join("Cargo.toml"),
+        format!("{}{}", cargo_toml, features),
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert!(!script.contains("cargo clippy"), "{}", script);
+
+    // Toggling a feature regenerates the hook without updating the version
+    let features = features.replace(
+        "\"run-cargo-test\"",
+        "\"run-cargo-test\", \"run-cargo-clippy\"",
+    );
+    fs::write(
+        root.join("Cargo.toml"),
+        format!("{}{}", cargo_toml, features),
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert!(script.contains("cargo clippy"), "{}", script);
+    assert_eq!(hook_backups(&root, "pre-push").len(), 1);
+
+    // So does changing the configuration. Ensure modified time and the name of backup differ from
+    // previous
+    thread::sleep(time::Duration::from_secs(1));
+    let config = "\n[package.metadata.husky.hooks]\npre-push = [\"clippy\"]\n";
+    fs::write(
+        root.join("Cargo.toml"),
+        format!("{}{}{}", cargo_toml, features, config),
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert!(!script.contains("cargo test"), "{}", script);
+    assert_eq!(hook_backups(&root, "pre-push").len(), 2);
+
+    // The hook is not regenerated when nothing changes
+    fs::remove_dir_all(root.join("target")).unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+    assert_eq!(get_hook_script(&root, "pre-push").unwrap(), script);
+    assert_eq!(hook_backups(&root, "pre-push").len(), 2);
+}
+
+#[test]
+fn force_regenerating_hooks() {
+    let root = cargo_project_for("force-regenerating-hooks");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(cargo_toml, "features = [\"precommit-hook\"]").unwrap();
+    let foreign = "#!/bin/sh\necho hello from other tool\n";
+    fs::write(hook_path(&root, "pre-commit"), foreign).unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+    let script = get_hook_script(&root, "pre-push").unwrap();
+
+    let edited = format!("{}echo edited by user\n", script);
+    fs::write(hook_path(&root, "pre-push"), &edited).unwrap();
+
+    // The edited hook is kept without the variable
+    fs::remove_dir_all(root.join("target")).unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+    assert_eq!(get_hook_script(&root, "pre-push").unwrap(), edited);
+
+    let output = command("cargo")
+        .arg("test")
+        .env("CARGO_HUSKY_FORCE", "1")
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(get_hook_script(&root, "pre-push").unwrap(), script);
+    assert_eq!(get_hook_script(&root, "pre-commit").unwrap(), foreign);
+}
+
+#[test]
+fn dont_install_hooks_env_var() {
+    let root = cargo_project_for("dont-install-hooks");
+    let output = command("cargo")
+        .arg("test")
+        .env("CARGO_HUSKY_DONT_INSTALL_HOOKS", "1")
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(get_hook_script(&root, "pre-push"), None);
+
+    // Hooks are installed once the variable is unset
+    run_cargo(&root, ["test"]).unwrap();
+    assert!(get_hook_script(&root, "pre-push").is_some());
+}
+
+#[test]
+fn skip_installing_hooks_in_ci() {
+    let root = cargo_project_for("skip-in-ci");
+    let cargo_test = |var: &str, value: &str| {
+        let output = command("cargo")
+            .arg("test")
+            .env(var, value)
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+    };
+
+    cargo_test("GITHUB_ACTIONS", "true");
+    assert_eq!(get_hook_script(&root, "pre-push"), None);
+    cargo_test("CI", "false");
+    assert!(get_hook_script(&root, "pre-push").is_some());
+
+    let root = cargo_project_for("install-in-ci");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(cargo_toml, "features = [\"install-in-ci\"]").unwrap();
+    let output = command("cargo")
+        .arg("test")
+        .env("CI", "true")
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    assert!(get_hook_script(&root, "pre-push").is_some());
+}
+
+#[test]
+fn transitive_dependency() {
+    let dev_dep = format!(
+        "[dev-dependencies.cargo-husky]\nversion = \"{}\"",
+        env!("CARGO_PKG_VERSION")
+    );
+    let upstream = cargo_project_for("transitive-dependency-upstream");
+    let manifest = fs::read_to_string(upstream.join("Cargo.toml")).unwrap();
+    let manifest = manifest.replacen("[dev-dependencies.", "[dependencies.", 1);
+    fs::write(upstream.join("Cargo.toml"), manifest).unwrap();
+
+    // cargo-husky is built as a dependency of the upstream crate
+    let root = cargo_project_for("transitive-dependency");
+    let manifest = fs::read_to_string(root.join("Cargo.toml")).unwrap();
+    let upstream_dep = format!(
+        "[dependencies.transitive-dependency-upstream]\npath = \"{}\"",
+        upstream.to_string_lossy().replace("\\", "\\\\")
+    );
+    let manifest = manifest.replacen(&dev_dep, &upstream_dep, 1);
+    fs::write(root.join("Cargo.toml"), &manifest).unwrap();
+    let output = run_cargo(&root, ["test"]).unwrap();
+    let stderr = str::from_utf8(&output.stderr).unwrap();
+    assert!(
+        stderr.contains("depends on cargo-husky directly"),
+        "{}",
+        stderr
+    );
+    assert_eq!(get_hook_script(&root, "pre-push"), None);
+
+    // Renamed dependency is also regarded as direct one
+    let renamed = format!(
+        "\n[dev-dependencies.husky]\npackage = \"cargo-husky\"\nversion = \"{}\"\n",
+        env!("CARGO_PKG_VERSION")
+    );
+    fs::write(root.join("Cargo.toml"), format!("{}{}", manifest, renamed)).unwrap();
+    fs::remove_dir_all(root.join("target")).unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+    assert!(get_hook_script(&root, "pre-push").is_some());
+}
+
+#[test]
+fn manifest_path_from_root_without_dependency() {
+    let root = cargo_project_for("manifest-path-root");
+    let dev_dep = format!(
+        "[dev-dependencies.cargo-husky]\nversion = \"{}\"",
+        env!("CARGO_PKG_VERSION")
+    );
+    let manifest = fs::read_to_string(root.join("Cargo.toml")).unwrap();
+    assert!(manifest.contains(&dev_dep), "{}", manifest);
+    fs::write(root.join("Cargo.toml"), manifest.replacen(&dev_dep, "", 1)).unwrap();
+
+    // Crate in the sub directory depends on cargo-husky but the root crate does not
+    let sub = cargo_project_for("manifest-path-sub");
+    fs::remove_dir_all(sub.join(".git")).unwrap();
+    fs::rename(&sub, root.join("sub")).unwrap();
+    let output = command("cargo")
+        .args(["test", "--manifest-path"])
+        .arg(root.join("sub").join("Cargo.toml"))
+        .env("PWD", &root)
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let stderr = str::from_utf8(&output.stderr).unwrap();
+    assert!(
+        !stderr.contains("depends on cargo-husky directly"),
+        "{}",
+        stderr
+    );
+    assert!(get_hook_script(&root, "pre-push").is_some());
+}
+
+#[test]
+fn unparsable_manifest_in_working_directory() {
+    let root = cargo_project_for("unparsable-manifest");
+    let tools = root.join("tools");
+    fs::create_dir_all(&tools).unwrap();
+    fs::write(tools.join("Cargo.toml"), "this is not = = toml\n").unwrap();
+
+    // Only the manifest of the working directory is broken. cargo reads the crate's manifest
+    let output = command("cargo")
+        .arg("test")
+        .arg("--manifest-path")
+        .arg(root.join("Cargo.toml"))
+        .env("PWD", &tools)
+        .current_dir(&tools)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let stderr = str::from_utf8(&output.stderr).unwrap();
+    assert!(
+        stderr
+            .contains("Installing hooks without checking that cargo-husky is a direct dependency"),
+        "{}",
+        stderr
+    );
+    assert!(get_hook_script(&root, "pre-push").is_some());
+}
+
+#[test]
+fn skip_docs_rs_and_package_verification() {
+    let root = cargo_project_for("skip-docs-rs");
+    let output = command("cargo")
+        .arg("test")
+        .env("DOCS_RS", "1")
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let stderr = str::from_utf8(&output.stderr).unwrap();
+    assert!(!stderr.contains("warning: cargo-husky"), "{}", stderr);
+    assert_eq!(get_hook_script(&root, "pre-push"), None);
+
+    // Older cargo verifies the packaged crate with the target directory in `target/package`
+    let root = cargo_project_for("skip-package-verification");
+    fs::create_dir_all(root.join("target")).unwrap();
+    fs::write(root.join("target").join("CACHEDIR.TAG"), "").unwrap();
+    let target = root
+        .join("target")
+        .join("package")
+        .join("foo-0.1.0")
+        .join("target");
+    let output = command("cargo")
+        .arg("test")
+        .env("CARGO_TARGET_DIR", &target)
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(get_hook_script(&root, "pre-push"), None);
+}
+
+#[test]
+fn parallel_installation() {
+    let root = cargo_project_for("parallel-installation");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "features = [\"precommit-hook\", \"postmerge-hook\", \"run-cargo-clippy\"]"
+    )
+    .unwrap();
+
+    // Build scripts are run concurrently with separate target directories
+    let builds = (0..4)
+        .map(|i| {
+            let root = root.clone();
+            thread::spawn(move || {
+                command("cargo")
+                    .arg("test")
+                    .env("CARGO_TARGET_DIR", root.join(format!("target-{}", i)))
+                    .current_dir(&root)
+                    .output()
+                    .unwrap()
+            })
+        })
+        .collect::<Vec<_>>();
+    for build in builds {
+        let output = build.join().unwrap();
+        assert!(output.status.success(), "{:?}", output);
+    }
+
+    for hook in &["pre-push", "pre-commit", "post-merge"] {
+        let script = get_hook_script(&root, hook).unwrap();
+        assert_eq!(script.matches("#!/bin/sh").count(), 1, "{}", script);
+        assert!(script.contains("cargo clippy"), "{}", script);
+        let output = command("sh")
+            .args(["-n", hook_path(&root, hook).to_str().unwrap()])
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+    }
+    let leftovers = fs::read_dir(root.join(".git").join("hooks"))
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .filter(|name| name.ends_with(".tmp"))
+        .collect::<Vec<_>>();
+    assert!(leftovers.is_empty(), "{:?}", leftovers);
+    assert!(!root.join(".git").join("cargo-husky.lock").exists());
+}
+
+#[test]
+fn stale_install_lock() {
+    let root = cargo_project_for("stale-install-lock");
+    let lock = root.join(".git").join("cargo-husky.lock");
+    fs::write(&lock, "").unwrap();
+    let stale = time::SystemTime::now() - time::Duration::from_secs(60);
+    File::options()
+        .write(true)
+        .open(&lock)
+        .unwrap()
+        .set_modified(stale)
+        .unwrap();
+
+    run_cargo(&root, ["test"]).unwrap();
+    assert!(get_hook_script(&root, "pre-push").is_some());
+    assert!(!lock.exists());
+    let leftovers = fs::read_dir(root.join(".git"))
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .filter(|name| name.starts_with("cargo-husky.lock"))
+        .collect::<Vec<_>>();
+    assert!(leftovers.is_empty(), "{:?}", leftovers);
+
+    // The lock kept fresh by its holder is not broken however long it is held
+    fs::write(&lock, "").unwrap();
+    let held = time::Duration::from_secs(12);
+    let holder = {
+        let lock = lock.clone();
+        thread::spawn(move || {
+            let start = time::Instant::now();
+            while start.elapsed() < held {
+                File::options()
+                    .write(true)
+                    .open(&lock)
+                    .unwrap()
+                    .set_modified(time::SystemTime::now())
+                    .unwrap();
+                thread::sleep(time::Duration::from_secs(1));
+            }
+            fs::remove_file(&lock).is_ok()
+        })
+    };
+    let start = time::Instant::now();
+    fs::remove_dir_all(root.join("target")).unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+    assert!(start.elapsed() >= held);
+    assert!(holder.join().unwrap());
+    assert!(!lock.exists());
+}
+
+#[test]
+fn modified_hook_not_overwritten() {
+    let root = cargo_project_for("modified-hook-not-overwritten");
+    run_cargo(&root, ["test"]).unwrap();
+    let script = get_hook_script(&root, "pre-push").unwrap();
+
+    // Simulate updating cargo-husky after the hook was edited by hand
+    let current = format!("set by cargo-husky v{}", env!("CARGO_PKG_VERSION"));
+    let prev_version = decrease_patch(SemVer::parse(env!("CARGO_PKG_VERSION")).unwrap());
+    let prev = format!("set by cargo-husky v{}", prev_version);
+    let edited = format!(
+        "{}echo edited by user\n",
+        script.replacen(&current, &prev, 1)
+    );
+    fs::write(hook_path(&root, "pre-push"), &edited).unwrap();
+    fs::remove_dir_all(root.join("target")).unwrap();
+
+    let output = run_cargo(&root, ["test"]).unwrap();
+    let stderr = str::from_utf8(&output.stderr).unwrap();
+    assert!(
+        stderr.contains("was modified after cargo-husky generated it"),
+        "{}",
+        stderr
+    );
+    assert_eq!(get_hook_script(&root, "pre-push").unwrap(), edited);
+    assert!(hook_backups(&root, "pre-push").is_empty());
+
+    let output = command("cargo")
+        .arg("test")
+        .env("CARGO_HUSKY_FORCE", "1")
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(get_hook_script(&root, "pre-push").unwrap(), script);
+}
+
+#[test]
+fn modified_hook_detected_by_script_hash() {
+    let root = cargo_project_for("modified-hook-script-hash");
+    run_cargo(&root, ["test"]).unwrap();
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    let current = format!("set by cargo-husky v{}", env!("CARGO_PKG_VERSION"));
+    let prev_version = decrease_patch(SemVer::parse(env!("CARGO_PKG_VERSION")).unwrap());
+    let prev = format!("set by cargo-husky v{}", prev_version);
+    let prev_script = script.replacen(&current, &prev, 1);
+
+    // The previous version may have computed the config hash from another part of the script
+    let config_hash = script
+        .lines()
+        .find_map(|l| l.strip_prefix("# Config hash: "))
+        .unwrap();
+    fs::write(
+        hook_path(&root, "pre-push"),
+        prev_script.replacen(config_hash, "0123456789abcdef", 1),
+    )
+    .unwrap();
+    fs::remove_dir_all(root.join("target")).unwrap();
+    let output = run_cargo(&root, ["test"]).unwrap();
+    let stderr = str::from_utf8(&output.stderr).unwrap();
+    assert!(!stderr.contains("was modified"), "{}", stderr);
+    assert_eq!(get_hook_script(&root, "pre-push").unwrap(), script);
+
+    // Edits in the part which is not covered by the config hash are also detected
+    let edited = prev_script.replacen(
+        "# >>> cargo-husky runtime\n",
+        "# >>> cargo-husky runtime\nPATH=\"$HOME/bin:$PATH\"\n",
+        1,
+    );
+    assert_ne!(edited, prev_script);
+    fs::write(hook_path(&root, "pre-push"), &edited).unwrap();
+    fs::remove_dir_all(root.join("target")).unwrap();
+    let output = run_cargo(&root, ["test"]).unwrap();
+    let stderr = str::from_utf8(&output.stderr).unwrap();
+    assert!(
+        stderr.contains("was modified after cargo-husky generated it"),
+        "{}",
+        stderr
+    );
+    assert_eq!(get_hook_script(&root, "pre-push").unwrap(), edited);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn non_utf8_cargo_dir() {
+    use std::os::unix::ffi::OsStrExt;
+
+    let root = cargo_project_for("non-utf8-cargo-dir");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "\n[package.metadata.husky.hooks]\npre-commit = [\"version\"]\n\n[package.metadata.husky.commands.version]\nrun = \"command -v cargo\""
+    )
+    .unwrap();
+
+    // cargo in the directory whose name is not valid UTF-8 builds the crate
+    let bin_dir = TMPDIR_ROOT
+        .join(ffi::OsStr::from_bytes(b"cargo-\xff"))
+        .join("bin");
+    fs::create_dir_all(&bin_dir).unwrap();
+    let cargo = bin_dir.join("cargo");
+    fs::copy(env::var("CARGO").unwrap(), &cargo).unwrap();
+    let output = command(&cargo)
+        .arg("test")
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let script = get_hook_script(&root, "pre-commit").unwrap();
+    assert!(script.contains("printf '"), "{}", script);
+
+    let home = root.join("home");
+    fs::create_dir_all(&home).unwrap();
+    let output = command("sh")
+        .arg(hook_path(&root, "pre-commit"))
+        .env("PATH", "/usr/bin:/bin")
+        .env("HOME", &home)
+        .env_remove("CARGO_HOME")
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let mut expected = cargo.as_os_str().as_bytes().to_vec();
+    expected.push(b'\n');
+    assert!(output.stdout.ends_with(&expected), "{:?}", output);
+}
+
+#[test]
+#[cfg(not(target_os = "windows"))]
+fn cargo_dir_not_part_of_config_hash() {
+    let root = cargo_project_for("cargo-dir-not-hashed");
+    run_cargo(&root, ["test"]).unwrap();
+    let script = get_hook_script(&root, "pre-push").unwrap();
+
+    // Simulate the hook installed by cargo of another toolchain
+    let cargo_dir = script
+        .lines()
+        .find_map(|l| l.trim().strip_prefix("PATH=\"$PATH:\""))
+        .unwrap_or_else(|| panic!("{}", script))
+        .to_string();
+    assert!(!cargo_dir.is_empty(), "{}", script);
+    let other = rehash_script(&script.replace(&cargo_dir, "/path/to/other/toolchain/bin"));
+    fs::write(hook_path(&root, "pre-push"), &other).unwrap();
+    fs::remove_dir_all(root.join("target")).unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+    assert_eq!(get_hook_script(&root, "pre-push").unwrap(), other);
+    assert!(hook_backups(&root, "pre-push").is_empty());
+
+    // The hook is not regarded as edited by hand when cargo-husky is updated
+    let current = format!("set by cargo-husky v{}", env!("CARGO_PKG_VERSION"));
+    let prev_version = decrease_patch(SemVer::parse(env!("CARGO_PKG_VERSION")).unwrap());
+    let prev = format!("set by cargo-husky v{}", prev_version);
+    fs::write(
+        hook_path(&root, "pre-push"),
+        other.replacen(&current, &prev, 1),
+    )
+    .unwrap();
+    fs::remove_dir_all(root.join("target")).unwrap();
+    let output = run_cargo(&root, ["test"]).unwrap();
+    let stderr = str::from_utf8(&output.stderr).unwrap();
+    assert!(!stderr.contains("was modified"), "{}", stderr);
+    assert_eq!(get_hook_script(&root, "pre-push").unwrap(), script);
+}
+
+#[test]
+fn warn_on_errors() {
+    let root = cargo_project_for("warn-on-errors");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "features = [\"warn-on-errors\"]\n\n[package.metadata.husky.hooks]\npre-push = [\"test\", \"fmtt\"]"
+    )
+    .unwrap();
+
+    let output = run_cargo(&root, ["test"]).unwrap();
+    let stderr = str::from_utf8(&output.stderr).unwrap();
+    assert!(
+        stderr.contains("Could not install Git hooks") && stderr.contains("unknown command 'fmtt'"),
+        "{}",
+        stderr
+    );
+    assert_eq!(get_hook_script(&root, "pre-push"), None);
+
+    // The variable makes the error fail the build regardless of the feature
+    let output = command("cargo")
+        .arg("test")
+        .env("CARGO_HUSKY_STRICT", "1")
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    assert!(!output.status.success(), "{:?}", output);
+    let stderr = str::from_utf8(&output.stderr).unwrap();
+    assert!(stderr.contains("unknown command 'fmtt'"), "{}", stderr);
+
+    let root = cargo_project_for("not-strict");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "\n[package.metadata.husky.hooks]\npre-push = [\"fmtt\"]"
+    )
+    .unwrap();
+    let output = command("cargo")
+        .arg("test")
+        .env("CARGO_HUSKY_STRICT", "0")
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(get_hook_script(&root, "pre-push"), None);
+}
+
+#[test]
+fn installation_summary() {
+    let root = cargo_project_for("installation-summary");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(cargo_toml, "features = [\"run-cargo-clippy\"]").unwrap();
+    let output = run_cargo(&root, ["test"]).unwrap();
+    let stderr = str::from_utf8(&output.stderr).unwrap();
+    assert!(
+        stderr.contains("cargo-husky installed pre-push hook (test, clippy)"),
+        "{}",
+        stderr
+    );
+
+    // Nothing is reported when the hook is already up to date
+    fs::remove_dir_all(root.join("target")).unwrap();
+    let output = run_cargo(&root, ["test"]).unwrap();
+    let stderr = str::from_utf8(&output.stderr).unwrap();
+    assert!(!stderr.contains("cargo-husky installed"), "{}", stderr);
+
+    let root = cargo_project_for("installation-summary-quiet");
+    let output = command("cargo")
+        .arg("test")
+        .env("CARGO_HUSKY_QUIET", "1")
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let stderr = str::from_utf8(&output.stderr).unwrap();
+    assert!(!stderr.contains("cargo-husky installed"), "{}", stderr);
+    assert!(get_hook_script(&root, "pre-push").is_some());
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn windows_gitdir_in_wsl() {
+    let root = cargo_project_for("windows-gitdir-in-wsl");
+    fs::remove_dir_all(root.join(".git")).unwrap();
+    fs::write(
+        root.join(".git"),
+        "gitdir: C:/cargo-husky-test/windows-gitdir-in-wsl/.git\n",
+    )
+    .unwrap();
+    let build = |wsl: bool| {
+        let mut cmd = command("cargo");
+        cmd.args(["test", "-vv"])
+            .env("CARGO_HUSKY_DEBUG", "1")
+            .env("PWD", &root)
+            .current_dir(&root);
+        if wsl {
+            cmd.env("WSL_DISTRO_NAME", "Ubuntu");
+        } else {
+            cmd.env_remove("WSL_DISTRO_NAME");
+        }
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "{:?}", output);
+        String::from_utf8(output.stderr).unwrap()
+    };
+
+    // Git for Windows wrote the path of the drive. It is read through the mount of the drive in WSL
+    let stderr = build(true);
+    assert!(
+        stderr.contains(
+            "cargo-husky: Git directory in .git file: \"/mnt/c/cargo-husky-test/windows-gitdir-in-wsl/.git\""
+        ),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.contains("Skipped installing Git hooks"),
+        "{}",
+        stderr
+    );
+
+    // The path is not translated outside WSL
+    let in_wsl = fs::read_to_string("/proc/sys/kernel/osrelease")
+        .is_ok_and(|r| r.to_ascii_lowercase().contains("microsoft"));
+    if !in_wsl {
+        fs::remove_dir_all(root.join("target")).unwrap();
+        let stderr = build(false);
+        assert!(!stderr.contains("/mnt/c/"), "{}", stderr);
+        assert!(
+            stderr.contains("C:/cargo-husky-test/windows-gitdir-in-wsl/.git\""),
+            "{}",
+            stderr
+        );
+    }
+}
+
+#[test]
+fn debug_logging() {
+    let root = cargo_project_for("debug-logging");
+    let output = command("cargo")
+        .args(["test", "-vv"])
+        .env("CARGO_HUSKY_DEBUG", "1")
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let stderr = str::from_utf8(&output.stderr).unwrap();
+    for log in &[
+        "cargo-husky: Searching Git directory from $OUT_DIR",
+        "cargo-husky: Found repository",
+        "cargo-husky: Hooks directory:",
+        "exists=false set_by_cargo_husky=false",
+        "cargo-husky: Generated pre-push hook script:",
+    ] {
+        assert!(stderr.contains(log), "{:?} in {}", log, stderr);
+    }
+
+    // Nothing is logged without the variable
+    fs::remove_dir_all(root.join("target")).unwrap();
+    let output = run_cargo(&root, ["test", "-vv"]).unwrap();
+    let stderr = str::from_utf8(&output.stderr).unwrap();
+    assert!(!stderr.contains("cargo-husky: "), "{}", stderr);
+}
+
+#[test]
+fn dry_run() {
+    let root = cargo_project_for("dry-run");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "features = [\"precommit-hook\", \"chain-existing-hooks\"]"
+    )
+    .unwrap();
+    let foreign = "#!/bin/sh\necho hello from other tool\n";
+    fs::write(hook_path(&root, "pre-commit"), foreign).unwrap();
+    let list_git_dir = || {
+        let mut entries = vec![];
+        let mut dirs = vec![root.join(".git")];
+        while let Some(dir) = dirs.pop() {
+            for entry in fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    dirs.push(path.clone());
+                }
+                entries.push(path);
+            }
+        }
+        entries.sort();
+        entries
+    };
+    let before = list_git_dir();
+
+    let output = command("cargo")
+        .arg("test")
+        .env("CARGO_HUSKY_DRY_RUN", "1")
+        .arg("-vv")
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let stderr = str::from_utf8(&output.stderr).unwrap();
+    let pre_push = hook_path(&root, "pre-push");
+    let pre_commit = hook_path(&root, "pre-commit");
+    for expected in &[
+        format!("cargo-husky: Would write {:?}:\n", pre_push),
+        format!(
+            "cargo-husky: Would move {:?} to {:?}",
+            pre_commit,
+            pre_commit.with_file_name("pre-commit.local")
+        ),
+        format!("cargo-husky: Would write {:?}:\n", pre_commit),
+        "cargo-husky would install pre-push hook (test)".to_string(),
+    ] {
+        assert!(
+            stderr.contains(expected.as_str()),
+            "{:?} in {}",
+            expected,
+            stderr
+        );
+    }
+
+    // Nothing in the Git directory is modified
+    assert_eq!(list_git_dir(), before);
+    assert_eq!(get_hook_script(&root, "pre-commit").unwrap(), foreign);
 }
 
 #[test]
-fn default_behavior() {
-    let root = cargo_project_for("default");
-    run_cargo(&root, &["test"]).unwrap();
+fn npm_husky_hooks() {
+    let husky_hook = "#!/bin/sh\n# husky\n\n# Hook created by Husky v4.3.8 (https://github.com/typicode/husky#readme)\n\n. \"$(dirname \"$0\")/husky.sh\"\n";
+
+    let root = cargo_project_for("npm-husky-hooks");
+    fs::write(hook_path(&root, "pre-push"), husky_hook).unwrap();
+    let output = run_cargo(&root, ["test"]).unwrap();
+    let stderr = str::from_utf8(&output.stderr).unwrap();
+    assert!(
+        stderr.contains("was installed by husky (npm)"),
+        "{}",
+        stderr
+    );
+    assert_eq!(get_hook_script(&root, "pre-push").unwrap(), husky_hook);
+
+    let root = cargo_project_for("replace-npm-husky-hooks");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(cargo_toml, "features = [\"replace-npm-husky-hooks\"]").unwrap();
+    fs::write(hook_path(&root, "pre-push"), husky_hook).unwrap();
+    run_cargo(&root, ["test"]).unwrap();
     let script = get_hook_script(&root, "pre-push").unwrap();
+    assert!(
+        script.contains("This hook was set by cargo-husky"),
+        "{}",
+        script
+    );
+    let backups = hook_backups(&root, "pre-push");
+    assert_eq!(backups.len(), 1, "{:?}", backups);
+    assert_eq!(fs::read_to_string(&backups[0]).unwrap(), husky_hook);
 
-    assert_eq!(script.lines().nth(0).unwrap(), "#!/bin/sh");
-    assert!(script
-        .lines()
-        .nth(2)
-        .unwrap()
-        .contains(format!("set by cargo-husky v{}", env!("CARGO_PKG_VERSION")).as_str()));
+    // Other foreign hooks are still preserved
+    let root = cargo_project_for("replace-npm-husky-hooks-only");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(cargo_toml, "features = [\"replace-npm-husky-hooks\"]").unwrap();
+    let foreign = "#!/bin/sh\necho hello from other tool\n";
+    fs::write(hook_path(&root, "pre-push"), foreign).unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+    assert_eq!(get_hook_script(&root, "pre-push").unwrap(), foreign);
+
+    let root = cargo_project_for("chain-npm-husky-hooks");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(cargo_toml, "features = [\"chain-existing-hooks\"]").unwrap();
+    fs::write(hook_path(&root, "pre-push"), husky_hook).unwrap();
+    run_cargo(&root, ["test"]).unwrap();
     assert_eq!(
-        script.lines().filter(|l| *l == "cargo test --all").count(),
-        1
+        get_hook_script(&root, "pre-push.local").unwrap(),
+        husky_hook
     );
-    assert!(script.lines().all(|l| !l.contains("cargo clippy")));
-
-    assert_eq!(get_hook_script(&root, "pre-commit"), None);
 }
 
 #[test]
-#[cfg(not(target_os = "windows"))]
-fn hook_file_is_executable() {
-    use std::os::unix::fs::PermissionsExt;
+fn import_pre_commit_config() {
+    let pre_commit_config = r#"# See https://pre-commit.com for more information
+default_stages: [pre-commit]
+repos:
+- repo: https://github.com/pre-commit/pre-commit-hooks
+  rev: v4.6.0
+  hooks:
+  - id: trailing-whitespace
+- repo: local
+  hooks:
+  - id: greet
+    name: Greet
+    entry: echo "hello from pre-commit"
+    args: ['it''s', --flag]
+    language: system
+    pass_filenames: false
+  - id: fmt
+    entry: echo formatted
+    language: system
+    files: \.rs$
+  - id: lint
+    entry: cargo clippy
+    args:
+      - --all-targets
+    language: system
+    stages: [push]
+  - id: black
+    entry: black
+    language: python
+"#;
 
-    let root = cargo_project_for("unit-permission");
-    run_cargo(&root, &["test"]).unwrap();
+    let root = cargo_project_for("import-pre-commit-config");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(cargo_toml, "features = [\"import-pre-commit\"]").unwrap();
+    fs::write(root.join(".pre-commit-config.yaml"), pre_commit_config).unwrap();
+    let output = run_cargo(&root, ["test"]).unwrap();
+    let stderr = str::from_utf8(&output.stderr).unwrap();
+    for warning in &[
+        "Hooks of repository 'https://github.com/pre-commit/pre-commit-hooks' were not imported",
+        "Filters `files` of hook 'fmt' were ignored",
+        "Hook 'black' was not imported since language 'python' is not supported",
+        "Hook 'fmt' is run without filenames as arguments",
+        "Hook 'lint' is run without filenames as arguments",
+    ] {
+        assert!(stderr.contains(warning), "{:?} in {}", warning, stderr);
+    }
+    assert!(
+        !stderr.contains("Hook 'greet' is run without filenames"),
+        "{}",
+        stderr
+    );
 
-    let prepush_path = hook_path(&root, "pre-push");
-    let mode = File::open(&prepush_path)
-        .unwrap()
-        .metadata()
-        .unwrap()
-        .permissions()
-        .mode();
-    assert_eq!(mode & 0o555, 0o555);
+    let script = get_hook_script(&root, "pre-commit").unwrap();
+    assert!(script.contains("echo formatted"), "{}", script);
+    assert!(!script.contains("cargo clippy"), "{}", script);
+    let output = run_hook(&root, "pre-commit");
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    assert!(output.status.success(), "{}", stdout);
+    assert!(
+        stdout.contains("hello from pre-commit it's --flag"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("formatted"), "{}", stdout);
+
+    // `fmt` is a builtin command so the imported one is renamed
+    assert!(!script.contains("cargo fmt"), "{}", script);
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert!(script.contains("cargo clippy --all-targets"), "{}", script);
+    // Imported commands are added to the commands enabled by features
+    let position = |cmd: &str| script.lines().position(|l| l == cmd).unwrap();
+    assert!(
+        position("cargo test --all") < position("cargo clippy --all-targets"),
+        "{}",
+        script
+    );
+
+    // The file is ignored without the feature
+    let root = cargo_project_for("ignore-pre-commit-config");
+    fs::write(root.join(".pre-commit-config.yaml"), pre_commit_config).unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+    assert_eq!(get_hook_script(&root, "pre-commit"), None);
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert!(!script.contains("cargo clippy"), "{}", script);
+
+    // Invalid YAML is reported with its line number
+    let root = cargo_project_for("invalid-pre-commit-config");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(cargo_toml, "features = [\"import-pre-commit\"]").unwrap();
+    fs::write(
+        root.join(".pre-commit-config.yaml"),
+        "repos:\n- repo: local\n  hooks: [\n",
+    )
+    .unwrap();
+    let err = run_cargo(&root, ["test"]).unwrap_err();
+    assert!(err.contains("line 3"), "{}", err);
 }
 
 #[test]
-fn change_features() {
-    let root = cargo_project_for("features");
+fn stale_hook_warning() {
+    let root = cargo_project_for("stale-hook-warning");
     let mut cargo_toml = open_cargo_toml(&root);
     writeln!(
         cargo_toml,
-        "default-features = false\nfeatures = [\"precommit-hook\", \"run-cargo-clippy\", \"run-cargo-check\", \"run-cargo-fmt\"]"
-    ).unwrap();
-    run_cargo(&root, &["test"]).unwrap();
+        "\n[package.metadata.husky.hooks]\npre-push = [\"greet\"]\n\n[package.metadata.husky.commands.greet]\nrun = \"echo hello\""
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
 
-    assert_eq!(get_hook_script(&root, "pre-push"), None);
+    let output = run_hook(&root, "pre-push");
+    assert!(output.status.success(), "{:?}", output);
+    let stderr = str::from_utf8(&output.stderr).unwrap();
+    assert!(!stderr.contains("cargo-husky:"), "{}", stderr);
 
-    let script = get_hook_script(&root, "pre-commit").unwrap();
-    assert!(script.lines().all(|l| l != "cargo test"));
-    assert_eq!(
-        script
-            .lines()
-            .filter(|l| *l == "cargo clippy -- -D warnings")
-            .count(),
-        1
-    );
-    assert_eq!(script.lines().filter(|l| *l == "cargo check").count(), 1);
-    assert_eq!(
-        script
-            .lines()
-            .filter(|l| *l == "cargo fmt -- --check")
-            .count(),
-        1
+    // Cargo.lock locks other version after the dependency was updated without building the crate
+    let lock_path = root.join("Cargo.lock");
+    let version = env!("CARGO_PKG_VERSION");
+    let lock = fs::read_to_string(&lock_path).unwrap();
+    let locked = format!("name = \"cargo-husky\"\nversion = \"{}\"", version);
+    assert!(lock.contains(&locked), "{}", lock);
+    let lock = lock.replace(&locked, "name = \"cargo-husky\"\nversion = \"99.0.0\"");
+    fs::write(&lock_path, lock).unwrap();
+
+    let output = run_hook(&root, "pre-push");
+    assert!(output.status.success(), "{:?}", output);
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    assert!(stdout.contains("hello"), "{}", stdout);
+    let stderr = str::from_utf8(&output.stderr).unwrap();
+    let warning = format!(
+        "This hook was generated by cargo-husky v{} but Cargo.lock locks v99.0.0",
+        version
     );
+    assert!(stderr.contains(&warning), "{}", stderr);
 }
 
 #[test]
-fn change_features_using_run_for_all() {
-    let root = cargo_project_for("features_using_run_for_all");
+fn self_healing_hooks() {
+    let root = cargo_project_for("self-healing-hooks");
     let mut cargo_toml = open_cargo_toml(&root);
     writeln!(
         cargo_toml,
-        "default-features = false\nfeatures = [\"precommit-hook\", \"run-for-all\", \"run-cargo-test\", \"run-cargo-check\", \"run-cargo-clippy\", \"run-cargo-fmt\"]"
-    ).unwrap();
-    run_cargo(&root, &["test"]).unwrap();
+        "features = [\"self-healing-hooks\"]\n\n[package.metadata.husky.hooks]\npre-push = [\"greet\"]\n\n[package.metadata.husky.commands.greet]\nrun = \"echo hello\""
+    )
+    .unwrap();
+    run_cargo(&root, ["test"]).unwrap();
 
-    assert_eq!(get_hook_script(&root, "pre-push"), None);
+    let output = run_hook(&root, "pre-push");
+    assert!(output.status.success(), "{:?}", output);
+    let stderr = str::from_utf8(&output.stderr).unwrap();
+    assert!(!stderr.contains("cargo-husky:"), "{}", stderr);
 
-    let script = get_hook_script(&root, "pre-commit").unwrap();
-    assert_eq!(
-        script.lines().filter(|l| *l == "cargo test --all").count(),
-        1
+    // The hook reinstalls hooks when the record of the installation is lost
+    let manifest = root.join(".git").join("cargo-husky.json");
+    fs::remove_file(&manifest).unwrap();
+    let output = run_hook(&root, "pre-push");
+    assert!(output.status.success(), "{:?}", output);
+    let stderr = str::from_utf8(&output.stderr).unwrap();
+    assert!(
+        stderr.contains("cargo-husky.json recording the installed hooks is missing. Reinstalling hooks with `cargo test --no-run`"),
+        "{}",
+        stderr
     );
+    assert!(manifest.exists());
+    let stdout = str::from_utf8(&output.stdout).unwrap();
     assert_eq!(
-        script
-            .lines()
-            .filter(|l| *l == "cargo clippy --all -- -D warnings")
-            .count(),
-        1
+        stdout.lines().filter(|l| *l == "hello").count(),
+        1,
+        "{}",
+        stdout
     );
-    assert_eq!(
-        script.lines().filter(|l| *l == "cargo check --all").count(),
-        1
+
+    // The hook is built only once when it is still outdated after reinstalling
+    let version = env!("CARGO_PKG_VERSION");
+    let lock_path = root.join("Cargo.lock");
+    let lock = fs::read_to_string(&lock_path).unwrap().replace(
+        &format!("name = \"cargo-husky\"\nversion = \"{}\"", version),
+        "name = \"cargo-husky\"\nversion = \"99.0.0\"",
     );
-    assert_eq!(
-        script
-            .lines()
-            .filter(|l| *l == "cargo fmt --all -- --check")
-            .count(),
-        1
+    fs::write(&lock_path, lock).unwrap();
+    let output = command("sh")
+        .arg(hook_path(&root, "pre-push"))
+        .env("CARGO_HUSKY_HEALED", "1")
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let stderr = str::from_utf8(&output.stderr).unwrap();
+    assert!(!stderr.contains("Reinstalling hooks"), "{}", stderr);
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    assert!(stdout.contains("hello"), "{}", stdout);
+
+    // Hooks without the feature only warn
+    let root = cargo_project_for("no-self-healing-hooks");
+    run_cargo(&root, ["test"]).unwrap();
+    fs::remove_file(root.join(".git").join("cargo-husky.json")).unwrap();
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert!(!script.contains("cargo test --no-run"), "{}", script);
+}
+
+#[test]
+fn newer_hook_not_downgraded() {
+    let root = cargo_project_for("newer-hook-not-downgraded");
+    run_cargo(&root, ["test"]).unwrap();
+
+    // The hook was installed by newer cargo-husky on another branch
+    let marker = format!(
+        "This hook was set by cargo-husky v{}:",
+        env!("CARGO_PKG_VERSION")
+    );
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert!(script.contains(&marker), "{}", script);
+    let newer = script.replace(&marker, "This hook was set by cargo-husky v99.0.0-beta.1:");
+    fs::write(hook_path(&root, "pre-push"), &newer).unwrap();
+
+    fs::remove_dir_all(root.join("target")).unwrap();
+    let output = run_cargo(&root, ["test"]).unwrap();
+    let stderr = str::from_utf8(&output.stderr).unwrap();
+    assert!(
+        stderr.contains("was installed by newer cargo-husky v99.0.0-beta.1. It was not downgraded"),
+        "{}",
+        stderr
+    );
+    assert_eq!(get_hook_script(&root, "pre-push").unwrap(), newer);
+    assert!(hook_backups(&root, "pre-push").is_empty());
+
+    // Older hook is updated
+    let older = script.replace(&marker, "This hook was set by cargo-husky v0.9.0:");
+    fs::write(hook_path(&root, "pre-push"), older).unwrap();
+    fs::remove_dir_all(root.join("target")).unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+    assert!(get_hook_script(&root, "pre-push")
+        .unwrap()
+        .contains(&marker));
+
+    fs::write(hook_path(&root, "pre-push"), &newer).unwrap();
+    let output = command("cargo")
+        .arg("test")
+        .env("CARGO_HUSKY_FORCE", "1")
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    assert!(get_hook_script(&root, "pre-push")
+        .unwrap()
+        .contains(&marker));
+}
+
+#[test]
+fn hook_with_syntax_error_not_written() {
+    let root = cargo_project_for("hook-with-syntax-error");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "\n[package.metadata.husky.hooks]\npre-push = [\"broken\"]\n\n[package.metadata.husky.commands.broken]\nrun = \"if true; then echo broken\""
+    )
+    .unwrap();
+    let err = run_cargo(&root, ["test"]).unwrap_err();
+    assert!(
+        err.contains("was not written since it has a syntax error"),
+        "{}",
+        err
     );
+    assert_eq!(get_hook_script(&root, "pre-push"), None);
+    assert!(fs::read_dir(root.join(".git").join("hooks"))
+        .unwrap()
+        .all(|e| !e.unwrap().file_name().to_string_lossy().ends_with(".tmp")));
+}
+
+#[test]
+fn hook_updated_when_config_file_changes() {
+    let root = cargo_project_for("config-file-changes");
+    let config = root.join(".cargo-husky.toml");
+    let hello = |message: &str| {
+        fs::write(
+            &config,
+            format!(
+                "[hooks]\npre-commit = [\"hello\"]\n\n[commands.hello]\nrun = \"echo {}\"",
+                message
+            ),
+        )
+        .unwrap();
+    };
+    hello("hello");
+    run_cargo(&root, ["test"]).unwrap();
+    let out = run_hook(&root, "pre-commit");
+    assert!(out.status.success(), "{:?}", out);
+    assert!(str::from_utf8(&out.stdout).unwrap().ends_with("\nhello\n"));
+
+    // The next build without cleaning the target directory applies the edited configuration
+    thread::sleep(time::Duration::from_secs(1));
+    hello("goodbye");
+    run_cargo(&root, ["test"]).unwrap();
+    let out = run_hook(&root, "pre-commit");
+    assert!(out.status.success(), "{:?}", out);
+    let stdout = str::from_utf8(&out.stdout).unwrap();
+    assert!(stdout.ends_with("\ngoodbye\n"), "{}", stdout);
+}
+
+#[test]
+fn user_hook_added_without_cleaning_build() {
+    let root = cargo_project_for("user-hook-added");
+    setup_user_hooks_feature(&root);
+    let hooks = root.join(".cargo-husky").join("hooks");
+    fs::create_dir_all(&hooks).unwrap();
+    let write_hook = |name: &str| {
+        let path = hooks.join(name);
+        fs::write(&path, format!("#!/bin/sh\necho {}\n", name)).unwrap();
+        #[cfg(not(target_os = "windows"))]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+    };
+    write_hook("pre-commit");
+    run_cargo(&root, ["test"]).unwrap();
+    assert!(hook_path(&root, "pre-commit").is_file());
+    assert!(!hook_path(&root, "pre-push").exists());
+
+    thread::sleep(time::Duration::from_secs(1));
+    write_hook("pre-push");
+    run_cargo(&root, ["test"]).unwrap();
+    let out = run_hook(&root, "pre-push");
+    assert!(out.status.success(), "{:?}", out);
+    assert_eq!(str::from_utf8(&out.stdout).unwrap(), "pre-push\n");
+}
+
+#[test]
+fn config_file_created_after_build() {
+    let root = cargo_project_for("config-file-created");
+    run_cargo(&root, &["test"]).unwrap();
+    assert!(!hook_path(&root, "pre-commit").exists());
+
+    // Watching the missing configuration file does not run the build script on every build
+    let output = run_cargo(&root, &["test", "-v"]).unwrap();
+    let stderr = str::from_utf8(&output.stderr).unwrap();
+    assert!(stderr.contains("Fresh cargo-husky"), "{}", stderr);
+
+    // The configuration file created after the first build is applied without cleaning the target
+    // directory
+    thread::sleep(time::Duration::from_secs(1));
+    fs::write(
+        root.join(".cargo-husky.toml"),
+        "[hooks]\npre-commit = [\"created\"]\n\n[commands.created]\nrun = \"echo created\"",
+    )
+    .unwrap();
+    run_cargo(&root, &["test"]).unwrap();
+    let out = run_hook(&root, "pre-commit");
+    assert!(out.status.success(), "{:?}", out);
+    let stdout = str::from_utf8(&out.stdout).unwrap();
+    assert!(stdout.ends_with("\ncreated\n"), "{}", stdout);
 }
 
 #[test]
@@ -326,6 +4509,45 @@ fn regenerate_hook_script_on_package_update() {
         .nth(2)
         .unwrap()
         .contains(format!("set by cargo-husky v{}", env!("CARGO_PKG_VERSION")).as_str()));
+
+    // Check the previous hook script was backed up
+    let backups = hook_backups(&root, "pre-push");
+    assert_eq!(backups.len(), 1, "{:?}", backups);
+    let backup = fs::read_to_string(&backups[0]).unwrap();
+    assert!(backup.contains(after.as_str()), "{}", backup);
+}
+
+fn hook_backups(root: &Path, hook: &str) -> Vec<PathBuf> {
+    let prefix = format!("{}.", hook);
+    fs::read_dir(hook_path(root, ""))
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .filter(|p| {
+            let name = p.file_name().unwrap().to_string_lossy();
+            name.starts_with(&prefix) && name.ends_with(".bak")
+        })
+        .collect()
+}
+
+#[test]
+fn overwrite_foreign_hooks() {
+    let root = cargo_project_for("overwrite-foreign-hooks");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(cargo_toml, "features = [\"overwrite-foreign-hooks\"]").unwrap();
+    let content = "#!/bin/sh\necho hand-tuned hook\n";
+    fs::write(hook_path(&root, "pre-push"), content).unwrap();
+
+    run_cargo(&root, ["test"]).unwrap();
+
+    let script = get_hook_script(&root, "pre-push").unwrap();
+    assert!(
+        script.contains("This hook was set by cargo-husky"),
+        "{}",
+        script
+    );
+    let backups = hook_backups(&root, "pre-push");
+    assert_eq!(backups.len(), 1, "{:?}", backups);
+    assert_eq!(fs::read_to_string(&backups[0]).unwrap(), content);
 }
 
 macro_rules! another_hook_test {
@@ -524,6 +4746,174 @@ fn copied_user_hooks_are_executable() {
     assert!(!hook_path(&root, "non-executable-file.txt").exists());
 }
 
+#[test]
+fn placeholders_in_user_hooks() {
+    let root = cargo_project_for("user-hooks-placeholders");
+    setup_user_hooks_feature(&root);
+
+    let user_hooks = TESTDIR.join("user-hooks-placeholders");
+    copy_dir_recursive(&user_hooks.join(".cargo-husky"), &root.join(".cargo-husky"));
+
+    run_cargo(&root, ["test"]).unwrap();
+
+    for name in &["pre-commit", "pre-push"] {
+        let s = get_hook_script(&root, name).unwrap();
+        let comment = format!("# Installed as {} hook for version 0.1.0", name);
+        assert!(s.lines().any(|l| l == comment), "{}", s);
+        // Paths are written with `/` also on Windows since sh regards `\\` as escape character
+        let cd = format!("cd \"{}\"", root.to_string_lossy().replace('\\', "/"));
+        assert!(s.lines().any(|l| l == cd), "{}", s);
+        assert!(!s.contains("{{"), "{}", s);
+    }
+
+    // Placeholders are usable by sh which runs the hooks, including sh of Git for Windows
+    let out = run_hook(&root, "pre-commit");
+    assert!(out.status.success(), "{:?}", out);
+    assert_eq!(
+        str::from_utf8(&out.stdout).unwrap(),
+        "Running pre-commit hook\n"
+    );
+}
+
+#[test]
+fn user_hooks_with_non_shell_shebang() {
+    let root = cargo_project_for("user-hooks-shebang");
+    setup_user_hooks_feature(&root);
+
+    let user_hooks = TESTDIR.join("user-hooks-shebang");
+    copy_dir_recursive(&user_hooks.join(".cargo-husky"), &root.join(".cargo-husky"));
+
+    run_cargo(&root, ["test"]).unwrap();
+
+    // Scripts not run by shell are copied without header
+    let s = get_hook_script(&root, "pre-commit").unwrap();
+    assert_eq!(
+        s,
+        "#!/usr/bin/env python3\n\nprint(\"This is a user script for pre-commit hook written in Python\")\n"
+    );
+    let s = get_hook_script(&root, "pre-push").unwrap();
+    assert!(!s.contains("cargo-husky"), "{}", s);
+
+    let s = get_hook_script(&root, "post-merge").unwrap();
+    assert_eq!(s.lines().next(), Some("#!/usr/bin/env bash"));
+    assert!(
+        s.lines()
+            .nth(2)
+            .unwrap()
+            .contains("This hook was set by cargo-husky"),
+        "{}",
+        s
+    );
+}
+
+#[test]
+fn rust_hooks() {
+    let root = cargo_project_for("rust-hooks");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"rust-hooks\"]"
+    )
+    .unwrap();
+
+    let rust_hooks = TESTDIR.join("rust-hooks");
+    copy_dir_recursive(&rust_hooks.join(".cargo-husky"), &root.join(".cargo-husky"));
+
+    run_cargo(&root, ["test"]).unwrap();
+
+    assert!(!hook_path(&root, "pre-push").exists());
+    let script = get_hook_script(&root, "commit-msg").unwrap();
+    assert!(script.contains("--bin 'commit-msg'"), "{}", script);
+
+    let output = command("sh")
+        .arg(hook_path(&root, "commit-msg"))
+        .arg(".git/COMMIT_EDITMSG")
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(
+        str::from_utf8(&output.stdout).unwrap(),
+        "This is a hook written in Rust with args [\".git/COMMIT_EDITMSG\"]\n"
+    );
+}
+
+#[test]
+fn user_hooks_not_downgraded() {
+    let root = cargo_project_for("user-hooks-not-downgraded");
+    setup_user_hooks_feature(&root);
+    let user_hooks = TESTDIR.join("user-hooks-shebang");
+    copy_dir_recursive(&user_hooks.join(".cargo-husky"), &root.join(".cargo-husky"));
+    run_cargo(&root, ["test"]).unwrap();
+
+    // The user hook was installed by newer cargo-husky on another branch
+    let marker = format!(
+        "This hook was set by cargo-husky v{}:",
+        env!("CARGO_PKG_VERSION")
+    );
+    let script = get_hook_script(&root, "post-merge").unwrap();
+    assert!(script.contains(&marker), "{}", script);
+    let newer = script.replace(&marker, "This hook was set by cargo-husky v99.0.0:");
+    fs::write(hook_path(&root, "post-merge"), &newer).unwrap();
+    fs::remove_dir_all(root.join("target")).unwrap();
+    let output = run_cargo(&root, ["test"]).unwrap();
+    let stderr = str::from_utf8(&output.stderr).unwrap();
+    assert!(
+        stderr.contains("was installed by newer cargo-husky v99.0.0. It was not downgraded"),
+        "{}",
+        stderr
+    );
+    assert_eq!(get_hook_script(&root, "post-merge").unwrap(), newer);
+    assert!(hook_backups(&root, "post-merge").is_empty());
+
+    // Copy of the Python script has no version comment but it is updated with its source
+    let python = "#!/usr/bin/env python3\n\nprint(\"updated\")\n";
+    let src = root.join(".cargo-husky").join("hooks").join("pre-commit");
+    fs::write(&src, python).unwrap();
+    fs::remove_dir_all(root.join("target")).unwrap();
+    run_cargo(&root, ["test"]).unwrap();
+    assert_eq!(get_hook_script(&root, "pre-commit").unwrap(), python);
+
+    // Hook put by other tool is not overwritten by the user hook
+    let root = cargo_project_for("user-hooks-foreign");
+    setup_user_hooks_feature(&root);
+    copy_dir_recursive(&user_hooks.join(".cargo-husky"), &root.join(".cargo-husky"));
+    let foreign = "#!/usr/bin/env python3\n\nprint(\"other tool\")\n";
+    fs::write(hook_path(&root, "pre-commit"), foreign).unwrap();
+    let output = run_cargo(&root, ["test"]).unwrap();
+    let stderr = str::from_utf8(&output.stderr).unwrap();
+    assert!(
+        stderr.contains("was put by other tool. cargo-husky did not install user hook pre-commit"),
+        "{}",
+        stderr
+    );
+    assert_eq!(get_hook_script(&root, "pre-commit").unwrap(), foreign);
+    let manifest = fs::read_to_string(root.join(".git").join("cargo-husky.json")).unwrap();
+    assert!(
+        manifest.contains("\"hooks\": [\"post-merge\", \"pre-push\"]\n"),
+        "{}",
+        manifest
+    );
+}
+
+#[test]
+fn rust_hooks_without_hook_binary() {
+    let root = cargo_project_for("rust-hooks-without-hook-binary");
+    let mut cargo_toml = open_cargo_toml(&root);
+    writeln!(
+        cargo_toml,
+        "default-features = false\nfeatures = [\"rust-hooks\"]"
+    )
+    .unwrap();
+
+    let err = run_cargo(&root, ["test"]).unwrap_err();
+    assert!(
+        err.contains("Hook crate is not found or it has no binary named after a hook"),
+        "Unexpected output on `cargo test`: {}",
+        err
+    );
+}
+
 #[test]
 fn empty_script_file_not_allowed() {
     let root = cargo_project_for("empty-user-hook");
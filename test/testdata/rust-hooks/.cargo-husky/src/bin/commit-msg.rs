@@ -0,0 +1,6 @@
+use std::env;
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    println!("This is a hook written in Rust with args {:?}", args);
+}